@@ -0,0 +1,74 @@
+use std::{
+    env,
+    io::{self, Write},
+    process::{Command, Stdio},
+};
+
+use anyhow::bail;
+
+/// Clipboard tools to try in order, with the arguments that make them read stdin into the
+/// clipboard.
+fn clipboard_commands() -> Vec<(&'static str, &'static [&'static str])> {
+    if cfg!(target_os = "macos") {
+        vec![("pbcopy", &[])]
+    } else if cfg!(windows) {
+        vec![("clip", &[])]
+    } else {
+        vec![
+            ("wl-copy", &[]),
+            ("xclip", &["-selection", "clipboard"]),
+            ("xsel", &["--clipboard", "--input"]),
+        ]
+    }
+}
+
+/// Copies `text` to the system clipboard through `$GHPRS_CLIPBOARD` if set, otherwise the first
+/// clipboard tool found, such as `pbcopy` on macOS or `wl-copy`, `xclip` and `xsel` elsewhere.
+pub fn copy(text: &str) -> anyhow::Result<()> {
+    if let Some(command) = env::var("GHPRS_CLIPBOARD")
+        .ok()
+        .filter(|command| !command.is_empty())
+    {
+        let mut shell = Command::new("sh");
+        shell.arg("-c").arg(&command);
+        return match pipe_to(shell, text) {
+            Ok(()) => Ok(()),
+            Err(e) => bail!("Failed to run GHPRS_CLIPBOARD command '{command}': {e}"),
+        };
+    }
+
+    for (program, args) in clipboard_commands() {
+        let mut command = Command::new(program);
+        command.args(args);
+        match pipe_to(command, text) {
+            Ok(()) => return Ok(()),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => continue,
+            Err(e) => bail!("Failed to copy with {program}: {e}"),
+        }
+    }
+
+    let programs: Vec<&str> = clipboard_commands()
+        .into_iter()
+        .map(|(program, _)| program)
+        .collect();
+    bail!(
+        "No clipboard tool found, install one of {} or set GHPRS_CLIPBOARD",
+        programs.join(", ")
+    )
+}
+
+fn pipe_to(mut command: Command, text: &str) -> io::Result<()> {
+    let mut child = command
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(text.as_bytes())?;
+    }
+
+    let status = child.wait()?;
+    if !status.success() {
+        return Err(io::Error::other(format!("exited with {status}")));
+    }
+    Ok(())
+}
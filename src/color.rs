@@ -0,0 +1,94 @@
+use std::{
+    env,
+    io::{self, IsTerminal},
+    sync::OnceLock,
+};
+
+use clap::ValueEnum;
+
+#[derive(ValueEnum, Clone, Copy, Debug, Default)]
+pub enum ColorChoice {
+    #[default]
+    Auto,
+    Always,
+    Never,
+}
+
+static ENABLED: OnceLock<bool> = OnceLock::new();
+
+/// Decides once per process whether output is colored, honouring the NO_COLOR convention in auto mode.
+pub fn init(choice: ColorChoice) {
+    let enabled = match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => {
+            env::var_os("NO_COLOR").is_none_or(|no_color| no_color.is_empty())
+                && io::stdout().is_terminal()
+        }
+    };
+
+    let _ = ENABLED.set(enabled);
+}
+
+fn enabled() -> bool {
+    ENABLED.get().copied().unwrap_or(false)
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum Style {
+    Red,
+    Green,
+    Dim,
+    Bold,
+}
+
+impl Style {
+    fn code(self) -> &'static str {
+        match self {
+            Style::Red => "31",
+            Style::Green => "32",
+            Style::Dim => "2",
+            Style::Bold => "1",
+        }
+    }
+
+    pub fn paint(self, s: &str) -> String {
+        paint_all(&[self], s)
+    }
+}
+
+/// Paints `s` with every style in `styles` at once, e.g. dimmed and red.
+pub fn paint_all(styles: &[Style], s: &str) -> String {
+    if enabled() && !styles.is_empty() {
+        let codes: Vec<&str> = styles.iter().map(|style| style.code()).collect();
+        format!("\x1b[{}m{s}\x1b[0m", codes.join(";"))
+    } else {
+        s.to_string()
+    }
+}
+
+/// Colors whole rows of a rendered table, `styles[i]` applying to the i-th record after the header.
+///
+/// Styling is applied after rendering so escape codes don't throw off the column widths.
+pub fn paint_table_rows(table: &str, styles: &[Vec<Style>]) -> String {
+    let mut records = 0;
+
+    table
+        .lines()
+        .map(|line| {
+            if !line.starts_with('|') {
+                return line.to_string();
+            }
+
+            records += 1;
+            match records {
+                1 => Style::Bold.paint(line),
+                record => match styles.get(record - 2) {
+                    Some(styles) => paint_all(styles, line),
+                    None => line.to_string(),
+                },
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
@@ -0,0 +1,355 @@
+use clap::{Arg, Command, ValueEnum, ValueHint};
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+pub enum Shell {
+    Bash,
+    Zsh,
+    Fish,
+}
+
+fn command_names(command: &Command) -> Vec<&str> {
+    std::iter::once(command.get_name())
+        .chain(command.get_all_aliases())
+        .collect()
+}
+
+/// Flags of `command` along with the possible values of its positional arguments.
+fn candidates(command: &Command) -> Vec<String> {
+    command
+        .get_arguments()
+        .flat_map(|arg| {
+            if arg.is_positional() {
+                return possible_values(arg);
+            }
+            flags(arg)
+        })
+        .chain(["--help".to_string(), "-h".to_string()])
+        .collect()
+}
+
+/// The long and short spellings of flag `arg`.
+fn flags(arg: &Arg) -> Vec<String> {
+    let long = arg.get_long().map(|long| format!("--{long}"));
+    let short = arg.get_short().map(|short| format!("-{short}"));
+    long.into_iter().chain(short).collect()
+}
+
+fn possible_values(arg: &Arg) -> Vec<String> {
+    if !arg.get_action().takes_values() {
+        return Vec::new();
+    }
+
+    arg.get_possible_values()
+        .iter()
+        .filter(|value| !value.is_hide_set())
+        .map(|value| value.get_name().to_string())
+        .collect()
+}
+
+/// Every subcommand below `command` along with the names leading to it, parents first.
+fn nested_subcommands(command: &Command) -> Vec<(Vec<&str>, &Command)> {
+    command
+        .get_subcommands()
+        .flat_map(|subcommand| {
+            let nested = nested_subcommands(subcommand)
+                .into_iter()
+                .map(|(mut path, nested)| {
+                    path.insert(0, subcommand.get_name());
+                    (path, nested)
+                });
+            std::iter::once((vec![subcommand.get_name()], subcommand)).chain(nested)
+        })
+        .collect()
+}
+
+/// Flags of `command` marked `global`, clap only copies them into subcommands when building.
+fn global_flags(command: &Command) -> Vec<String> {
+    command
+        .get_arguments()
+        .filter(|arg| arg.is_global_set())
+        .flat_map(flags)
+        .collect()
+}
+
+fn bash(command: &Command, bin_name: &str) -> String {
+    let function_name = format!("_{}", bin_name.replace('-', "_"));
+    let subcommands = nested_subcommands(command);
+    let arguments: Vec<&Arg> = command
+        .get_arguments()
+        .chain(
+            subcommands
+                .iter()
+                .flat_map(|(_, subcommand)| subcommand.get_arguments()),
+        )
+        .filter(|arg| !arg.is_positional())
+        .collect();
+
+    let mut script = format!(
+        "{function_name}() {{\n    local cur prev path skip\n    cur=\"${{COMP_WORDS[COMP_CWORD]}}\"\n    prev=\"${{COMP_WORDS[COMP_CWORD-1]}}\"\n    path=\"\"\n    skip=\"\"\n    for word in \"${{COMP_WORDS[@]:1:COMP_CWORD-1}}\"; do\n        if [[ -n \"$skip\" ]]; then\n            skip=\"\"\n            continue\n        fi\n"
+    );
+
+    // The word after a flag taking a value is that value, even when it names a subcommand
+    let mut value_flags: Vec<String> = arguments
+        .iter()
+        .filter(|arg| arg.get_action().takes_values())
+        .flat_map(|arg| flags(arg))
+        .collect();
+    value_flags.sort();
+    value_flags.dedup();
+    if !value_flags.is_empty() {
+        script += &format!(
+            "        case \"$word\" in\n            {}) skip=1; continue ;;\n        esac\n",
+            value_flags.join("|")
+        );
+    }
+
+    script += "        case \"$path:$word\" in\n";
+    for (path, subcommand) in &subcommands {
+        let parent = path[..path.len() - 1].join(" ");
+        let patterns: Vec<String> = command_names(subcommand)
+            .into_iter()
+            .map(|name| format!("\"{parent}:{name}\""))
+            .collect();
+        script += &format!(
+            "            {}) path=\"{}\" ;;\n",
+            patterns.join("|"),
+            path.join(" ")
+        );
+    }
+    script += "        esac\n    done\n\n    case \"$prev\" in\n";
+
+    let mut completed_flags = Vec::new();
+    for arg in &arguments {
+        let Some(long) = arg.get_long() else {
+            continue;
+        };
+        if completed_flags.contains(&long) || !arg.get_action().takes_values() {
+            continue;
+        }
+        completed_flags.push(long);
+
+        let values = possible_values(arg);
+        let reply = if !values.is_empty() {
+            format!(
+                "COMPREPLY=($(compgen -W \"{}\" -- \"$cur\"))",
+                values.join(" ")
+            )
+        } else if let ValueHint::AnyPath | ValueHint::FilePath | ValueHint::DirPath =
+            arg.get_value_hint()
+        {
+            "COMPREPLY=($(compgen -f -- \"$cur\"))".to_string()
+        } else {
+            "COMPREPLY=()".to_string()
+        };
+        script += &format!("        --{long}) {reply}; return ;;\n");
+    }
+    script += "    esac\n\n    case \"$path\" in\n";
+
+    let globals = global_flags(command);
+    let nodes = std::iter::once((Vec::new(), command)).chain(subcommands.iter().cloned());
+    for (path, node) in nodes {
+        let mut words: Vec<String> = node
+            .get_subcommands()
+            .flat_map(command_names)
+            .map(str::to_string)
+            .chain(candidates(node))
+            .collect();
+        if !path.is_empty() {
+            words.extend(globals.iter().cloned());
+        }
+        script += &format!(
+            "        \"{}\") COMPREPLY=($(compgen -W \"{}\" -- \"$cur\")) ;;\n",
+            path.join(" "),
+            words.join(" ")
+        );
+    }
+    script += &format!("    esac\n}}\n\ncomplete -F {function_name} {bin_name}\n");
+
+    script
+}
+
+fn zsh(command: &Command, bin_name: &str) -> String {
+    format!(
+        "#compdef {bin_name}\n\nautoload -U bashcompinit && bashcompinit\n\n{}",
+        bash(command, bin_name)
+    )
+}
+
+fn fish_escape(s: &str) -> String {
+    s.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+/// Fish condition matching a command line that has reached the subcommand at `path`.
+fn fish_condition(command: &Command, path: &[&str]) -> String {
+    let mut node = command;
+    let mut conditions = Vec::new();
+    for name in path {
+        node = node
+            .find_subcommand(name)
+            .expect("paths come from the command's subcommands");
+        conditions.push(format!(
+            "__fish_seen_subcommand_from {}",
+            command_names(node).join(" ")
+        ));
+    }
+    conditions.join("; and ")
+}
+
+fn fish(command: &Command, bin_name: &str) -> String {
+    let mut script = String::new();
+    let subcommands = nested_subcommands(command);
+
+    for (path, subcommand) in &subcommands {
+        let (_, parents) = path
+            .split_last()
+            .expect("paths name at least the subcommand");
+        let condition = match parents {
+            [] => "__fish_use_subcommand".to_string(),
+            parents => {
+                let parent = parents
+                    .iter()
+                    .try_fold(command, |node, name| node.find_subcommand(name))
+                    .expect("paths come from the command's subcommands");
+                let siblings: Vec<&str> =
+                    parent.get_subcommands().flat_map(command_names).collect();
+                format!(
+                    "{}; and not __fish_seen_subcommand_from {}",
+                    fish_condition(command, parents),
+                    siblings.join(" ")
+                )
+            }
+        };
+        let about = subcommand
+            .get_about()
+            .map(|about| fish_escape(&about.to_string()))
+            .unwrap_or_default();
+        for name in command_names(subcommand) {
+            script +=
+                &format!("complete -c {bin_name} -f -n '{condition}' -a '{name}' -d '{about}'\n");
+        }
+    }
+
+    let argument_sets = std::iter::once((Vec::new(), command))
+        .chain(subcommands.iter().cloned())
+        .map(|(path, node)| {
+            let condition = match path.is_empty() {
+                true => "__fish_use_subcommand".to_string(),
+                false => fish_condition(command, &path),
+            };
+            (condition, node)
+        });
+
+    for (condition, node) in argument_sets {
+        for arg in node.get_arguments() {
+            if arg.is_positional() && possible_values(arg).is_empty() {
+                continue;
+            }
+
+            // Global flags are valid after any subcommand, so complete them everywhere
+            let mut line = match arg.is_global_set() {
+                true => format!("complete -c {bin_name}"),
+                false => format!("complete -c {bin_name} -n '{condition}'"),
+            };
+            if let Some(long) = arg.get_long() {
+                line += &format!(" -l {long}");
+            }
+            if let Some(short) = arg.get_short() {
+                line += &format!(" -s {short}");
+            }
+            let values = possible_values(arg);
+            if !values.is_empty() {
+                line += &format!(" -x -a '{}'", values.join(" "));
+            }
+            if let Some(help) = arg.get_help() {
+                line += &format!(" -d '{}'", fish_escape(&help.to_string()));
+            }
+            script += &line;
+            script += "\n";
+        }
+    }
+
+    script
+}
+
+/// Generates a completion script for `shell` covering the subcommands, aliases and flags of `command`.
+pub fn generate(shell: Shell, command: &Command, bin_name: &str) -> String {
+    match shell {
+        Shell::Bash => bash(command, bin_name),
+        Shell::Zsh => zsh(command, bin_name),
+        Shell::Fish => fish(command, bin_name),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Args;
+    use clap::CommandFactory;
+    use std::process::Command as Process;
+
+    /// What the bash script offers for the command line `words`, the last one being completed.
+    fn complete(words: &[&str]) -> Vec<String> {
+        let script = bash(&Args::command(), "ghp");
+        let quoted: Vec<String> = words.iter().map(|word| format!("'{word}'")).collect();
+        let invocation = format!(
+            "{script}\nCOMP_WORDS=(ghp {})\nCOMP_CWORD={}\n_ghp\nprintf '%s\\n' \"${{COMPREPLY[@]}}\"\n",
+            quoted.join(" "),
+            words.len()
+        );
+
+        let output = Process::new("bash")
+            .args(["-c", &invocation])
+            .output()
+            .unwrap();
+        assert!(
+            output.status.success(),
+            "{}",
+            String::from_utf8_lossy(&output.stderr)
+        );
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[test]
+    fn completes_subcommands() {
+        let completions = complete(&["fe"]);
+
+        assert_eq!(completions, vec!["fetch", "fetch-acked"]);
+    }
+
+    #[test]
+    fn completes_nested_subcommands() {
+        assert_eq!(
+            complete(&["config", ""])[..4],
+            ["get", "set", "unset", "check"]
+        );
+        assert_eq!(complete(&["repo", "r"]), vec!["rm", "remove"]);
+    }
+
+    #[test]
+    fn completes_global_flags_after_subcommands() {
+        let completions = complete(&["config", "set", "--"]);
+
+        assert!(completions.contains(&"--profile".to_string()));
+        assert!(completions.contains(&"--no-pager".to_string()));
+    }
+
+    #[test]
+    fn completes_flag_values() {
+        assert_eq!(complete(&["--sort", "re"]), vec!["repo"]);
+    }
+
+    #[test]
+    fn flag_values_are_not_taken_for_subcommands() {
+        let completions = complete(&["--sort", "repo", "ac"]);
+
+        assert_eq!(completions, vec!["ack"]);
+        assert_eq!(
+            complete(&["--group-by", "repo", "a"]),
+            vec!["ack", "a", "archived", "ar"]
+        );
+    }
+}
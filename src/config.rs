@@ -0,0 +1,610 @@
+use std::{collections::HashMap, path::Path};
+
+use anyhow::{anyhow, bail};
+use clap::Subcommand;
+use futures::future::join_all;
+use serde::Deserialize;
+use toml_edit::{table, value, Array, ArrayOfTables, DocumentMut, Item, Table, Value};
+
+use crate::{color::Style, gh_client::GithubClient, Config, Profile};
+
+#[derive(Subcommand, Debug)]
+pub enum ConfigCommand {
+    #[clap(about = "print the value of a config key")]
+    Get { key: String },
+    #[clap(about = "set a config key, repositories accepts several values")]
+    Set {
+        key: String,
+        #[arg(required = true)]
+        values: Vec<String>,
+    },
+    #[clap(about = "remove a config key")]
+    Unset { key: String },
+    #[clap(about = "verify the author and repositories against github")]
+    Check {},
+}
+
+#[derive(Subcommand, Debug)]
+pub enum RepoCommand {
+    #[clap(about = "start tracking a repository and fetch its prs")]
+    Add { repository: String },
+    #[clap(alias = "remove", about = "stop tracking a repository")]
+    Rm { repository: String },
+    #[clap(alias = "ls", about = "list tracked repositories")]
+    List {},
+}
+
+#[derive(Clone, Copy, Debug)]
+enum KeyKind {
+    String,
+    StringList,
+    Integer,
+}
+
+const KEYS: &[(&str, KeyKind)] = &[
+    ("author", KeyKind::String),
+    ("repositories", KeyKind::StringList),
+    ("session_state_file", KeyKind::String),
+    ("fixture_file", KeyKind::String),
+    ("ack_ttl", KeyKind::Integer),
+    ("stale_after", KeyKind::Integer),
+    ("required_reviewers", KeyKind::StringList),
+    ("base_branch", KeyKind::String),
+    ("archive_retention", KeyKind::Integer),
+];
+
+fn key_kind(key: &str) -> anyhow::Result<KeyKind> {
+    KEYS.iter()
+        .find(|(known_key, _)| *known_key == key)
+        .map(|(_, kind)| *kind)
+        .ok_or_else(|| {
+            let known_keys: Vec<&str> = KEYS.iter().map(|(key, _)| *key).collect();
+            anyhow!(
+                "Unknown config key '{key}', expected one of {}",
+                known_keys.join(", ")
+            )
+        })
+}
+
+fn read_document(config_path: &Path) -> anyhow::Result<DocumentMut> {
+    let contents = match std::fs::read_to_string(config_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => bail!("Failed to read config file {}: {e}", config_path.display()),
+    };
+
+    contents
+        .parse()
+        .map_err(|e| anyhow!("Could not parse config: {e}"))
+}
+
+/// The config with every key optional, so a file can be filled in one key at a time.
+#[derive(Deserialize)]
+struct PartialConfig {
+    #[serde(flatten)]
+    _top_level: Profile,
+    #[serde(default, rename = "profiles")]
+    _profiles: HashMap<String, Profile>,
+}
+
+fn write_document(config_path: &Path, document: &DocumentMut) -> anyhow::Result<()> {
+    let contents = document.to_string();
+    if let Err(e) = toml::from_str::<PartialConfig>(&contents) {
+        bail!("Refusing to write invalid config: {}", e.message())
+    }
+
+    if let Some(parent) = config_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    std::fs::write(config_path, contents)?;
+
+    Ok(())
+}
+
+/// The table holding the keys of `profile`, or the top level table when no profile is selected.
+fn section<'a>(
+    document: &'a mut DocumentMut,
+    profile: Option<&str>,
+) -> anyhow::Result<&'a mut Table> {
+    let Some(name) = profile else {
+        return Ok(document.as_table_mut());
+    };
+
+    let profiles = document
+        .entry("profiles")
+        .or_insert(table())
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("Config key 'profiles' is not a table"))?;
+    profiles.set_implicit(true);
+
+    profiles
+        .entry(name)
+        .or_insert(table())
+        .as_table_mut()
+        .ok_or_else(|| anyhow!("Config key 'profiles.{name}' is not a table"))
+}
+
+fn print_item(item: &Item) {
+    match item.as_array() {
+        Some(array) => {
+            for element in array.iter() {
+                match element.as_str() {
+                    Some(s) => println!("{s}"),
+                    None => println!("{element}"),
+                }
+            }
+        }
+        None => match item.as_str() {
+            Some(s) => println!("{s}"),
+            None => println!("{}", item.to_string().trim()),
+        },
+    }
+}
+
+async fn check_config(config_path: &Path, profile: Option<&str>) -> anyhow::Result<()> {
+    let contents = std::fs::read_to_string(config_path)?;
+    let config = toml::from_str::<Config>(&contents)
+        .map_err(|e| anyhow!("Could not parse config: {e}"))?
+        .with_profile(profile)?;
+    let github_client = GithubClient::new().await?;
+
+    let mut problems = 0;
+    let mut report = |ok: bool, subject: String, error: Option<String>| {
+        if ok {
+            println!("{} {subject}", Style::Green.paint("[ok]  "));
+        } else {
+            problems += 1;
+            println!("{} {subject}", Style::Red.paint("[fail]"));
+            if let Some(error) = error {
+                println!("       {error}");
+            }
+        }
+    };
+
+    let mut authors: Vec<String> = config.repository_authors().into_values().collect();
+    authors.sort();
+    authors.dedup();
+    authors.retain(|author| *author != config.author);
+    authors.insert(0, config.author.clone());
+
+    for author in authors {
+        let result = github_client.view_user(&author).await;
+        report(
+            result.is_ok(),
+            format!("author {author}"),
+            result
+                .err()
+                .map(|_| "no github user with this login".to_string()),
+        );
+    }
+
+    let repositories = config.repository_names();
+    let mut repositories: Vec<&String> = repositories.iter().collect();
+    repositories.sort();
+    let results = join_all(
+        repositories
+            .iter()
+            .map(|repository| github_client.view_repository(repository)),
+    )
+    .await;
+
+    for (repository, result) in repositories.into_iter().zip(results) {
+        report(
+            result.is_ok(),
+            format!("repository {repository}"),
+            result
+                .err()
+                .map(|_| "repository does not exist or is not accessible".to_string()),
+        );
+    }
+
+    if problems > 0 {
+        bail!("Found {problems} problems in the config")
+    }
+    Ok(())
+}
+
+/// Edits the config file in place so comments and formatting of untouched keys are preserved,
+/// keys are read and written under `[profiles.<name>]` when a profile is selected.
+pub async fn run_config_command(
+    command: &ConfigCommand,
+    config_path: &Path,
+    profile: Option<&str>,
+) -> anyhow::Result<()> {
+    if let ConfigCommand::Check {} = command {
+        return check_config(config_path, profile).await;
+    }
+
+    let mut document = read_document(config_path)?;
+
+    match command {
+        ConfigCommand::Get { key } => {
+            key_kind(key)?;
+            match section(&mut document, profile)?.get(key) {
+                Some(item) => print_item(item),
+                None => bail!("Config key '{key}' is not set"),
+            }
+        }
+        ConfigCommand::Set { key, values } => {
+            let section = section(&mut document, profile)?;
+            let item = match (key_kind(key)?, values.as_slice()) {
+                (KeyKind::StringList, values) if key == "repositories" => {
+                    set_repositories(section, values);
+                    return write_document(config_path, &document);
+                }
+                (KeyKind::StringList, values) => {
+                    let mut array = Array::new();
+                    for v in values {
+                        array.push(v.as_str());
+                    }
+                    value(array)
+                }
+                (KeyKind::String, [v]) => value(v.as_str()),
+                (KeyKind::Integer, [v]) => {
+                    let integer: i64 = v
+                        .parse()
+                        .map_err(|e| anyhow!("Config key '{key}' must be an integer: {e}"))?;
+                    value(integer)
+                }
+                (_, _) => bail!("Config key '{key}' takes a single value"),
+            };
+
+            section.insert(key, item);
+            write_document(config_path, &document)?;
+        }
+        ConfigCommand::Unset { key } => {
+            key_kind(key)?;
+            if section(&mut document, profile)?.remove(key).is_none() {
+                bail!("Config key '{key}' is not set")
+            }
+            write_document(config_path, &document)?;
+        }
+        ConfigCommand::Check {} => unreachable!("checked before reading the document"),
+    }
+
+    Ok(())
+}
+
+/// The `repositories` item of `profile`, either an array of names and inline tables or an array
+/// of `[[repositories]]` tables.
+fn repositories_item<'a>(
+    document: &'a mut DocumentMut,
+    profile: Option<&str>,
+) -> anyhow::Result<&'a mut Item> {
+    // A profile without its own list inherits the top level one, so start from a copy of it
+    let inherited = document
+        .get("repositories")
+        .filter(|_| profile.is_some())
+        .cloned()
+        .unwrap_or(value(Array::new()));
+
+    Ok(section(document, profile)?
+        .entry("repositories")
+        .or_insert(inherited))
+}
+
+fn entry_name(entry: &Value) -> Option<&str> {
+    entry
+        .as_str()
+        .or_else(|| entry.as_inline_table()?.get("name")?.as_str())
+}
+
+/// Replaces the repositories of `section` with `names`. Entries that stay keep their author, and
+/// `[[repositories]]` tables keep their comments.
+fn set_repositories(section: &mut Table, names: &[String]) {
+    match section.get_mut("repositories") {
+        Some(Item::ArrayOfTables(repositories)) => {
+            let mut kept = ArrayOfTables::new();
+            for name in names {
+                let existing = repositories
+                    .iter()
+                    .find(|r| r.get("name").and_then(Item::as_str) == Some(name.as_str()));
+                kept.push(existing.cloned().unwrap_or_else(|| {
+                    let mut table = Table::new();
+                    table.insert("name", value(name.as_str()));
+                    table
+                }));
+            }
+            *repositories = kept;
+        }
+        Some(Item::Value(Value::Array(repositories))) => {
+            let mut kept: Array = names
+                .iter()
+                .map(|name| {
+                    repositories
+                        .iter()
+                        .find(|r| entry_name(r) == Some(name.as_str()))
+                        .cloned()
+                        .unwrap_or_else(|| name.as_str().into())
+                })
+                .collect();
+            kept.fmt();
+            *repositories = kept;
+        }
+        _ => {
+            section.insert(
+                "repositories",
+                value(names.iter().map(String::as_str).collect::<Array>()),
+            );
+        }
+    }
+}
+
+/// Adds `repository` to the configured repositories, preserving the rest of the file.
+pub fn add_repository(
+    config_path: &Path,
+    profile: Option<&str>,
+    repository: &str,
+) -> anyhow::Result<()> {
+    if !repository.contains('/') {
+        bail!("Expected a repository in owner/name form, got '{repository}'")
+    }
+
+    let mut document = read_document(config_path)?;
+    let already_tracked = match repositories_item(&mut document, profile)? {
+        Item::Value(Value::Array(repositories)) => {
+            let already_tracked = repositories
+                .iter()
+                .any(|r| entry_name(r) == Some(repository));
+            if !already_tracked {
+                repositories.push(repository);
+            }
+            already_tracked
+        }
+        Item::ArrayOfTables(repositories) => {
+            let already_tracked = repositories
+                .iter()
+                .any(|r| r.get("name").and_then(Item::as_str) == Some(repository));
+            if !already_tracked {
+                let mut table = Table::new();
+                table.insert("name", value(repository));
+                repositories.push(table);
+            }
+            already_tracked
+        }
+        _ => bail!("Config key 'repositories' is not an array"),
+    };
+    if already_tracked {
+        bail!("Repository {repository} is already tracked")
+    }
+
+    write_document(config_path, &document)
+}
+
+/// Removes `repository` from the configured repositories, preserving the rest of the file.
+pub fn remove_repository(
+    config_path: &Path,
+    profile: Option<&str>,
+    repository: &str,
+) -> anyhow::Result<()> {
+    let mut document = read_document(config_path)?;
+    let removed = match repositories_item(&mut document, profile)? {
+        Item::Value(Value::Array(repositories)) => {
+            let index = repositories
+                .iter()
+                .position(|r| entry_name(r) == Some(repository));
+            index
+                .map(|index| {
+                    let removed = repositories.remove(index);
+                    // The next entry takes over the removed one's leading whitespace so a list
+                    // stays formatted like `["a", "b"]` rather than `[ "b"]`
+                    if let (Some(prefix), Some(next)) = (
+                        removed.decor().prefix().cloned(),
+                        repositories.get_mut(index),
+                    ) {
+                        next.decor_mut().set_prefix(prefix);
+                    }
+                })
+                .is_some()
+        }
+        Item::ArrayOfTables(repositories) => {
+            let index = repositories
+                .iter()
+                .position(|r| r.get("name").and_then(Item::as_str) == Some(repository));
+            index.map(|index| repositories.remove(index)).is_some()
+        }
+        _ => bail!("Config key 'repositories' is not an array"),
+    };
+    if !removed {
+        bail!("Repository {repository} is not tracked")
+    }
+
+    write_document(config_path, &document)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    /// A config file in a scratch directory, removed once the test is done.
+    struct ConfigFile {
+        path: PathBuf,
+    }
+
+    impl ConfigFile {
+        fn new(name: &str, contents: &str) -> ConfigFile {
+            let path = std::env::temp_dir()
+                .join(format!("ghprs-config-{name}-{}.toml", std::process::id()));
+            std::fs::write(&path, contents).unwrap();
+            ConfigFile { path }
+        }
+
+        fn contents(&self) -> String {
+            std::fs::read_to_string(&self.path).unwrap()
+        }
+
+        fn run(&self, command: ConfigCommand, profile: Option<&str>) -> anyhow::Result<()> {
+            smol::block_on(run_config_command(&command, &self.path, profile))
+        }
+    }
+
+    impl Drop for ConfigFile {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+
+    const CONFIG: &str = "# who to track\nauthor = \"me\"\nrepositories = [\"org/a\"]\n";
+
+    fn set(key: &str, values: &[&str]) -> ConfigCommand {
+        ConfigCommand::Set {
+            key: key.to_string(),
+            values: values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn set_and_unset_keep_comments() {
+        let config = ConfigFile::new("set", CONFIG);
+
+        config.run(set("ack_ttl", &["7"]), None).unwrap();
+        config
+            .run(set("repositories", &["org/a", "org/b"]), None)
+            .unwrap();
+        assert_eq!(
+            config.contents(),
+            "# who to track\nauthor = \"me\"\nrepositories = [\"org/a\", \"org/b\"]\nack_ttl = 7\n"
+        );
+
+        config
+            .run(
+                ConfigCommand::Unset {
+                    key: "ack_ttl".to_string(),
+                },
+                None,
+            )
+            .unwrap();
+        assert_eq!(
+            config.contents(),
+            "# who to track\nauthor = \"me\"\nrepositories = [\"org/a\", \"org/b\"]\n"
+        );
+    }
+
+    #[test]
+    fn set_rejects_invalid_values() {
+        let config = ConfigFile::new("invalid", CONFIG);
+
+        assert!(config.run(set("ack_ttl", &["soon"]), None).is_err());
+        assert!(config.run(set("author", &["me", "you"]), None).is_err());
+        assert!(config.run(set("colour", &["red"]), None).is_err());
+        assert!(config
+            .run(
+                ConfigCommand::Unset {
+                    key: "ack_ttl".to_string()
+                },
+                None
+            )
+            .is_err());
+        assert_eq!(config.contents(), CONFIG);
+    }
+
+    #[test]
+    fn set_writes_under_the_profile() {
+        let config = ConfigFile::new("profile", CONFIG);
+
+        config.run(set("author", &["bot"]), Some("work")).unwrap();
+
+        assert_eq!(
+            config.contents(),
+            format!("{CONFIG}\n[profiles.work]\nauthor = \"bot\"\n")
+        );
+    }
+
+    #[test]
+    fn repositories_are_added_and_removed() {
+        let config = ConfigFile::new("repositories", CONFIG);
+
+        add_repository(&config.path, None, "org/b").unwrap();
+        assert_eq!(
+            config.contents(),
+            "# who to track\nauthor = \"me\"\nrepositories = [\"org/a\", \"org/b\"]\n"
+        );
+        assert!(add_repository(&config.path, None, "org/b").is_err());
+        assert!(add_repository(&config.path, None, "b").is_err());
+
+        remove_repository(&config.path, None, "org/a").unwrap();
+        assert_eq!(
+            config.contents(),
+            "# who to track\nauthor = \"me\"\nrepositories = [\"org/b\"]\n"
+        );
+        assert!(remove_repository(&config.path, None, "org/a").is_err());
+    }
+
+    #[test]
+    fn repositories_with_authors_are_matched_by_name() {
+        let config = ConfigFile::new(
+            "tables",
+            "author = \"me\"\n\n[[repositories]]\nname = \"org/a\"\nauthor = \"bot\"\n",
+        );
+
+        assert!(add_repository(&config.path, None, "org/a").is_err());
+        add_repository(&config.path, None, "org/b").unwrap();
+        remove_repository(&config.path, None, "org/a").unwrap();
+
+        assert_eq!(
+            config.contents(),
+            "author = \"me\"\n\n[[repositories]]\nname = \"org/b\"\n"
+        );
+    }
+
+    #[test]
+    fn setting_repositories_keeps_tables_that_stay() {
+        let config = ConfigFile::new(
+            "set-tables",
+            "author = \"me\"\n\n# bots\n[[repositories]]\nname = \"org/a\"\nauthor = \"bot\"\n\n[[repositories]]\nname = \"org/c\"\n",
+        );
+
+        config
+            .run(set("repositories", &["org/a", "org/b"]), None)
+            .unwrap();
+
+        assert_eq!(
+            config.contents(),
+            "author = \"me\"\n\n# bots\n[[repositories]]\nname = \"org/a\"\nauthor = \"bot\"\n\n[[repositories]]\nname = \"org/b\"\n"
+        );
+    }
+
+    #[test]
+    fn setting_repositories_keeps_inline_authors() {
+        let config = ConfigFile::new(
+            "set-inline",
+            "author = \"me\"\nrepositories = [{ name = \"org/a\", author = \"bot\" }, \"org/c\"]\n",
+        );
+
+        config
+            .run(set("repositories", &["org/b", "org/a"]), None)
+            .unwrap();
+
+        assert_eq!(
+            config.contents(),
+            "author = \"me\"\nrepositories = [\"org/b\", { name = \"org/a\", author = \"bot\" }]\n"
+        );
+    }
+
+    #[test]
+    fn keys_can_be_set_one_at_a_time() {
+        let config = ConfigFile::new("empty", "");
+
+        config.run(set("author", &["me"]), None).unwrap();
+        config.run(set("ack_ttl", &["7"]), Some("work")).unwrap();
+        assert!(config.run(set("ack_ttl", &["-1"]), None).is_err());
+
+        assert_eq!(
+            config.contents(),
+            "author = \"me\"\n\n[profiles.work]\nack_ttl = 7\n"
+        );
+    }
+
+    #[test]
+    fn profile_repositories_start_from_the_top_level_ones() {
+        let config = ConfigFile::new("inherit", CONFIG);
+
+        add_repository(&config.path, Some("work"), "org/b").unwrap();
+
+        assert_eq!(
+            config.contents(),
+            format!("{CONFIG}\n[profiles.work]\nrepositories = [\"org/a\", \"org/b\"]\n")
+        );
+    }
+}
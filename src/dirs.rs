@@ -0,0 +1,65 @@
+use std::{env, path::PathBuf};
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+}
+
+fn home_directory() -> Option<PathBuf> {
+    if cfg!(windows) {
+        env_path("USERPROFILE").or_else(|| env_path("HOME"))
+    } else {
+        env_path("HOME")
+    }
+}
+
+/// Resolves `xdg_var`, then the platform's convention, then `unix_fallback` under the home directory.
+/// Without any of those the current directory is used so a missing HOME never panics.
+fn platform_directory(
+    xdg_var: &str,
+    windows_var: &str,
+    macos_subdirectory: &str,
+    unix_fallback: &str,
+) -> PathBuf {
+    if let Some(directory) = env_path(xdg_var) {
+        return directory;
+    }
+
+    let platform_directory = if cfg!(windows) {
+        env_path(windows_var).map(|directory| directory.join("ghprs"))
+    } else if cfg!(target_os = "macos") {
+        home_directory().map(|home| home.join(macos_subdirectory).join("ghprs"))
+    } else {
+        home_directory().map(|home| home.join(unix_fallback))
+    };
+
+    platform_directory.unwrap_or_default()
+}
+
+/// `$XDG_CONFIG_HOME`, `%APPDATA%\ghprs`, `~/Library/Application Support/ghprs` or `~/.config`.
+pub fn config_directory() -> PathBuf {
+    platform_directory(
+        "XDG_CONFIG_HOME",
+        "APPDATA",
+        "Library/Application Support",
+        ".config",
+    )
+}
+
+/// `$XDG_STATE_HOME`, `%LOCALAPPDATA%\ghprs`, `~/Library/Application Support/ghprs` or `~/.local/state`.
+pub fn state_directory() -> PathBuf {
+    platform_directory(
+        "XDG_STATE_HOME",
+        "LOCALAPPDATA",
+        "Library/Application Support",
+        ".local/state",
+    )
+}
+
+/// Where older versions kept session state, regardless of platform.
+pub fn legacy_state_directory() -> PathBuf {
+    env_path("XDG_CONFIG_HOME")
+        .or_else(|| home_directory().map(|home| home.join(".config")))
+        .unwrap_or_default()
+}
@@ -0,0 +1,152 @@
+use std::{fs::OpenOptions, process::ExitCode};
+
+use chrono::Local;
+use futures::future::join_all;
+
+use crate::{
+    color::Style,
+    gh_client::{GithubClient, GithubClientError},
+    load_config,
+    migrations::{parse_state, STATE_VERSION},
+    session_config_path, session_state_path, Args,
+};
+
+struct Doctor {
+    failures: usize,
+}
+
+impl Doctor {
+    fn pass(&self, check: &str) {
+        println!("{} {check}", Style::Green.paint("[ok]  "));
+    }
+
+    fn fail(&mut self, check: &str, fix: &str) {
+        self.failures += 1;
+        println!("{} {check}", Style::Red.paint("[fail]"));
+        println!("       fix: {fix}");
+    }
+}
+
+/// Runs every diagnostic, printing a fix for each failure, and exits non-zero if any failed.
+pub async fn run_doctor(args: &Args) -> ExitCode {
+    let mut doctor = Doctor { failures: 0 };
+
+    let github_client = match GithubClient::new().await {
+        Ok(github_client) => {
+            doctor.pass("gh cli is installed and authenticated");
+            Some(github_client)
+        }
+        Err(GithubClientError::CannotFindGithubCLI) => {
+            doctor.fail(
+                "gh cli is not in PATH",
+                "install it from https://cli.github.com and make sure it is in PATH",
+            );
+            None
+        }
+        Err(e) => {
+            doctor.fail(&e.to_string(), "run 'gh auth login'");
+            None
+        }
+    };
+
+    if let Some(github_client) = &github_client {
+        match github_client.rate_limit().await {
+            Ok(rate_limit) if rate_limit.remaining == 0 => doctor.fail(
+                "github api quota is exhausted",
+                &format!(
+                    "wait until it resets at {}",
+                    rate_limit.reset.with_timezone(&Local)
+                ),
+            ),
+            Ok(rate_limit) => doctor.pass(&format!(
+                "github api quota has {} of {} requests left",
+                rate_limit.remaining, rate_limit.limit
+            )),
+            Err(e) => doctor.fail(
+                &format!("could not read the github api quota: {e}"),
+                "check that 'gh api rate_limit' works",
+            ),
+        }
+    }
+
+    let config_path = session_config_path(args);
+    let config = match load_config(args) {
+        Ok(config) => {
+            doctor.pass(&format!("config {} parses", config_path.display()));
+            Some(config)
+        }
+        Err(e) => {
+            doctor.fail(
+                &format!("config {}: {e}", config_path.display()),
+                "run 'ghp init' or fix the file with 'ghp config set'",
+            );
+            None
+        }
+    };
+
+    if let (Some(github_client), Some(config)) = (&github_client, &config) {
+        let repositories = config.repository_names();
+        let mut repositories: Vec<&String> = repositories.iter().collect();
+        repositories.sort();
+
+        let results = join_all(
+            repositories
+                .iter()
+                .map(|repository| github_client.view_repository(repository)),
+        )
+        .await;
+
+        for (repository, result) in repositories.into_iter().zip(results) {
+            match result {
+                Ok(_) => doctor.pass(&format!("repository {repository} is reachable")),
+                Err(_) => doctor.fail(
+                    &format!("repository {repository} is not reachable"),
+                    &format!("check the spelling, or that 'gh repo view {repository}' works"),
+                ),
+            }
+        }
+    }
+
+    let state_path = session_state_path(args);
+    if state_path.exists() {
+        match std::fs::read_to_string(&state_path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| parse_state(&contents).map_err(|e| e.to_string()))
+        {
+            Ok((_, version)) if version < STATE_VERSION => doctor.pass(&format!(
+                "state {} is valid, version {version} will be migrated to {STATE_VERSION}",
+                state_path.display()
+            )),
+            Ok(_) => doctor.pass(&format!("state {} is valid", state_path.display())),
+            Err(e) => doctor.fail(
+                &format!("state {} is invalid: {e}", state_path.display()),
+                "move the file aside or run 'ghp clear-session'",
+            ),
+        }
+
+        match OpenOptions::new().append(true).open(&state_path) {
+            Ok(_) => doctor.pass(&format!("state {} is writable", state_path.display())),
+            Err(e) => doctor.fail(
+                &format!("state {} is not writable: {e}", state_path.display()),
+                "check the file's permissions",
+            ),
+        }
+    } else {
+        match state_path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() && !parent.is_dir() => doctor.fail(
+                &format!("state directory {} does not exist", parent.display()),
+                &format!("create it with 'mkdir -p {}'", parent.display()),
+            ),
+            _ => doctor.pass(&format!(
+                "state {} will be created on first run",
+                state_path.display()
+            )),
+        }
+    }
+
+    if doctor.failures == 0 {
+        ExitCode::SUCCESS
+    } else {
+        ExitCode::FAILURE
+    }
+}
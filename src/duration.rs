@@ -0,0 +1,160 @@
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Duration, Local, NaiveDate, Utc};
+
+/// Parses human friendly durations such as `30m`, `2h`, `7d` or `1w`.
+pub fn parse_duration(s: &str) -> anyhow::Result<Duration> {
+    let s = s.trim();
+    let Some(unit_start) = s.find(|c: char| !c.is_ascii_digit()) else {
+        bail!("Duration '{s}' is missing a unit, expected one of s, m, h, d, w")
+    };
+
+    let (amount, unit) = s.split_at(unit_start);
+    let amount: i64 = amount
+        .parse()
+        .map_err(|e| anyhow!("Invalid duration amount in '{s}': {e}"))?;
+
+    let duration = match unit {
+        "s" => Duration::try_seconds(amount),
+        "m" => Duration::try_minutes(amount),
+        "h" => Duration::try_hours(amount),
+        "d" => Duration::try_days(amount),
+        "w" => Duration::try_weeks(amount),
+        _ => bail!("Unknown duration unit '{unit}' in '{s}', expected one of s, m, h, d, w"),
+    };
+    duration.ok_or_else(|| anyhow!("Duration '{s}' is too long"))
+}
+
+/// The time `duration` from now, failing instead of panicking when that is past the latest
+/// representable date.
+pub fn from_now(duration: Duration) -> anyhow::Result<DateTime<Utc>> {
+    Utc::now().checked_add_signed(duration).ok_or_else(|| {
+        anyhow!(
+            "{} from now is too far in the future",
+            format_duration(duration)
+        )
+    })
+}
+
+/// Parses the start of a time window, either a duration back from now such as `24h`, a date
+/// such as `2024-01-01` taken as local midnight, or an RFC 3339 timestamp.
+pub fn parse_since(s: &str) -> anyhow::Result<DateTime<Utc>> {
+    let s = s.trim();
+    if let Ok(date) = NaiveDate::parse_from_str(s, "%Y-%m-%d") {
+        return date
+            .and_hms_opt(0, 0, 0)
+            .and_then(|midnight| midnight.and_local_timezone(Local).earliest())
+            .map(|midnight| midnight.with_timezone(&Utc))
+            .ok_or_else(|| anyhow!("Midnight on '{s}' does not exist in the local timezone"));
+    }
+    if let Ok(timestamp) = DateTime::parse_from_rfc3339(s) {
+        return Ok(timestamp.with_timezone(&Utc));
+    }
+
+    match parse_duration(s) {
+        Ok(duration) => Utc::now()
+            .checked_sub_signed(duration)
+            .ok_or_else(|| anyhow!("Time window '{s}' reaches too far into the past")),
+        Err(_) => bail!("Invalid time window '{s}', expected a duration such as 24h or a date such as 2024-01-01"),
+    }
+}
+
+/// Formats a duration using its two most significant units, e.g. `3d 4h` or `12m 5s`.
+pub fn format_duration(duration: Duration) -> String {
+    let seconds = duration.num_seconds().max(0);
+    let units = [("d", 86_400), ("h", 3_600), ("m", 60), ("s", 1)];
+
+    let parts: Vec<String> = units
+        .iter()
+        .scan(seconds, |remaining, (unit, unit_seconds)| {
+            let amount = *remaining / unit_seconds;
+            *remaining %= unit_seconds;
+            Some((amount, unit))
+        })
+        .skip_while(|(amount, _)| *amount == 0)
+        .take(2)
+        .filter(|(amount, _)| *amount > 0)
+        .map(|(amount, unit)| format!("{amount}{unit}"))
+        .collect();
+
+    if parts.is_empty() {
+        "0s".to_string()
+    } else {
+        parts.join(" ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_each_unit() {
+        assert_eq!(parse_duration("45s").unwrap(), Duration::seconds(45));
+        assert_eq!(parse_duration("30m").unwrap(), Duration::minutes(30));
+        assert_eq!(parse_duration("2h").unwrap(), Duration::hours(2));
+        assert_eq!(parse_duration(" 7d ").unwrap(), Duration::days(7));
+        assert_eq!(parse_duration("1w").unwrap(), Duration::weeks(1));
+    }
+
+    #[test]
+    fn rejects_malformed_durations() {
+        assert!(parse_duration("7").is_err());
+        assert!(parse_duration("d").is_err());
+        assert!(parse_duration("7y").is_err());
+        assert!(parse_duration("-7d").is_err());
+    }
+
+    #[test]
+    fn rejects_overflowing_durations() {
+        assert!(parse_duration("999999999999999w").is_err());
+        assert!(parse_duration("99999999999999999999d").is_err());
+
+        let duration = parse_duration("999999999d").unwrap();
+        assert!(from_now(duration).is_err());
+    }
+
+    #[test]
+    fn parses_since_as_a_duration_date_or_timestamp() {
+        let since = parse_since("24h").unwrap();
+        let expected = Utc::now() - Duration::hours(24);
+        assert!((expected - since).abs() < Duration::seconds(5));
+
+        let midnight = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap()
+            .and_local_timezone(Local)
+            .unwrap();
+        assert_eq!(parse_since("2024-01-01").unwrap(), midnight);
+
+        assert_eq!(
+            parse_since("2024-01-01T12:00:00+02:00").unwrap(),
+            "2024-01-01T10:00:00Z".parse::<DateTime<Utc>>().unwrap()
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_since() {
+        assert!(parse_since("yesterday").is_err());
+        assert!(parse_since("2024-13-01").is_err());
+        assert!(parse_since("999999999d").is_err());
+    }
+
+    #[test]
+    fn formats_the_two_most_significant_units() {
+        assert_eq!(format_duration(Duration::seconds(0)), "0s");
+        assert_eq!(format_duration(Duration::seconds(-5)), "0s");
+        assert_eq!(
+            format_duration(Duration::minutes(12) + Duration::seconds(5)),
+            "12m 5s"
+        );
+        assert_eq!(
+            format_duration(Duration::days(3) + Duration::hours(4) + Duration::minutes(1)),
+            "3d 4h"
+        );
+        assert_eq!(
+            format_duration(Duration::days(2) + Duration::minutes(1)),
+            "2d"
+        );
+    }
+}
@@ -0,0 +1,146 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+
+use crate::gh_client::GithubPRStatus;
+
+#[derive(Clone, Copy, Debug)]
+pub enum Delimiter {
+    Comma,
+    Tab,
+}
+
+pub const PR_RECORD_HEADER: [&str; 7] = [
+    "id",
+    "title",
+    "repository",
+    "created_at",
+    "latest_review_time",
+    "reviews",
+    "reviewers",
+];
+
+fn timestamp(time: Option<DateTime<Utc>>) -> String {
+    time.map(|time| time.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_default()
+}
+
+/// The core fields of a pr, in the order of `PR_RECORD_HEADER`.
+pub fn pr_record(pr: &GithubPRStatus) -> Vec<String> {
+    vec![
+        pr.id.clone(),
+        pr.title.clone(),
+        pr.repository.clone(),
+        timestamp(pr.created_at),
+        timestamp(pr.latest_review_time()),
+        pr.reviews.len().to_string(),
+        pr.reviewers().join(" "),
+    ]
+}
+
+fn field(value: &str, delimiter: Delimiter) -> String {
+    match delimiter {
+        Delimiter::Comma if value.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", value.replace('"', "\"\""))
+        }
+        Delimiter::Comma => value.to_string(),
+        // TSV has no quoting, so characters that would break the row are replaced.
+        Delimiter::Tab => value.replace(['\t', '\n', '\r'], " "),
+    }
+}
+
+/// Renders `records` as CSV or TSV with a header row.
+pub fn delimited(header: &[&str], records: &[Vec<String>], delimiter: Delimiter) -> String {
+    let separator = match delimiter {
+        Delimiter::Comma => ",",
+        Delimiter::Tab => "\t",
+    };
+
+    std::iter::once(
+        header
+            .iter()
+            .map(|h| h.to_string())
+            .collect::<Vec<String>>(),
+    )
+    .chain(records.iter().cloned())
+    .map(|record| {
+        record
+            .iter()
+            .map(|value| field(value, delimiter))
+            .collect::<Vec<String>>()
+            .join(separator)
+    })
+    .collect::<Vec<String>>()
+    .join("\n")
+}
+
+fn markdown_escape(s: &str) -> String {
+    s.replace('[', "\\[").replace(']', "\\]")
+}
+
+/// Renders prs as a markdown bullet list with links and reviewers, for pasting into chat.
+pub fn markdown(prs: &[GithubPRStatus]) -> String {
+    prs.iter()
+        .map(|pr| {
+            let title = markdown_escape(&pr.title);
+            let link = match pr.web_url() {
+                Some(url) => format!("[{title}]({url})"),
+                None => title,
+            };
+            let reviewers = pr.reviewers();
+
+            if reviewers.is_empty() {
+                format!("- {link} ({})", pr.repository)
+            } else {
+                format!(
+                    "- {link} ({}), reviewed by {}",
+                    pr.repository,
+                    reviewers.join(", ")
+                )
+            }
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+/// Renders prs as tab separated `id, repository, number, latest review time` lines without a header.
+///
+/// This format is part of the command line interface contract and must not change between
+/// releases, fields may only ever be appended.
+pub fn porcelain(prs: &[GithubPRStatus]) -> String {
+    prs.iter()
+        .map(|pr| {
+            [
+                pr.id.clone(),
+                pr.repository.clone(),
+                pr.number
+                    .map(|number| number.to_string())
+                    .unwrap_or_default(),
+                timestamp(pr.latest_review_time()),
+            ]
+            .join("\t")
+        })
+        .collect::<Vec<String>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn markdown_links_prs_without_a_url() {
+        let pr: GithubPRStatus = serde_json::from_value(json!({
+            "id": "a",
+            "reviews": [],
+            "title": "Fix [flaky] test",
+            "repository": "org/a",
+            "number": 7,
+        }))
+        .unwrap();
+
+        assert_eq!(
+            markdown(&[pr]),
+            "- [Fix \\[flaky\\] test](https://github.com/org/a/pull/7) (org/a)"
+        );
+    }
+}
@@ -0,0 +1,63 @@
+use std::collections::HashMap;
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    gh_client::{GithubClientError, GithubPRState, GithubPRStatus, PrFetcher, Result},
+    prs::Session,
+};
+
+/// Open prs recorded from a session's repositories, served back in place of github by
+/// implementing `PrFetcher`.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Fixture {
+    pub recorded_at: DateTime<Utc>,
+    /// Open prs by repository, repositories without open prs are recorded with none.
+    pub repositories: HashMap<String, Vec<GithubPRStatus>>,
+}
+
+impl Fixture {
+    /// Records the open prs of every repository configured in `session`, failing if any
+    /// repository can't be fetched so a fixture is never silently missing one.
+    pub async fn record<F: PrFetcher>(fetcher: &F, session: &Session) -> Result<Fixture> {
+        let mut repositories = HashMap::new();
+        for repository in &session.repositories {
+            let prs = fetcher
+                .fetch_prs(repository, session.repository_author(repository))
+                .await?;
+            repositories.insert(repository.clone(), prs);
+        }
+
+        Ok(Fixture {
+            recorded_at: Utc::now(),
+            repositories,
+        })
+    }
+
+    fn repository_prs(&self, repository: &str) -> Result<&Vec<GithubPRStatus>> {
+        self.repositories
+            .get(repository)
+            .ok_or_else(|| GithubClientError::NotRecorded {
+                operation: format!("fetching prs of {repository}"),
+            })
+    }
+}
+
+impl PrFetcher for Fixture {
+    /// Prs are recorded for the author configured at the time, so `author` is not checked.
+    async fn fetch_prs(&self, repository: &str, _author: &str) -> Result<Vec<GithubPRStatus>> {
+        self.repository_prs(repository).cloned()
+    }
+
+    /// Only open prs are recorded, so the state of any other pr is unknown.
+    async fn fetch_pr_state(&self, repository: &str, number: u64) -> Result<GithubPRState> {
+        self.repository_prs(repository)?
+            .iter()
+            .find(|pr| pr.number == Some(number))
+            .map(|_| GithubPRState::Open)
+            .ok_or_else(|| GithubClientError::NotRecorded {
+                operation: format!("looking up the state of {repository}#{number}"),
+            })
+    }
+}
@@ -0,0 +1,32 @@
+/// Scores `candidate` against `query` as a case-insensitive subsequence match.
+///
+/// Returns `None` when the characters of `query` don't all appear in order in `candidate`,
+/// otherwise the length of the matched span, so tighter matches score lower.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<usize> {
+    let candidate: Vec<char> = candidate.to_lowercase().chars().collect();
+    let query: Vec<char> = query.to_lowercase().chars().collect();
+
+    let first = query.first()?;
+    (0..candidate.len())
+        .filter(|&start| candidate[start] == *first)
+        .filter_map(|start| {
+            let mut position = start;
+            for c in &query[1..] {
+                position += 1 + candidate[position + 1..].iter().position(|x| x == c)?;
+            }
+            Some(position - start + 1)
+        })
+        .min()
+}
+
+/// Returns the indices of `candidates` matching `query`, best match first.
+pub fn fuzzy_filter<S: AsRef<str>>(query: &str, candidates: &[S]) -> Vec<usize> {
+    let mut matches: Vec<(usize, usize)> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| Some((fuzzy_score(query, candidate.as_ref())?, index)))
+        .collect();
+    matches.sort();
+
+    matches.into_iter().map(|(_, index)| index).collect()
+}
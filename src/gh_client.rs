@@ -1,21 +1,152 @@
-use serde::{Deserialize, Serialize};
-use std::process::Stdio;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{
+    env,
+    future::Future,
+    path::{Path, PathBuf},
+    process::Stdio,
+    time::Instant,
+};
 
 use chrono::{DateTime, Utc};
 use smol::process::Command;
 use thiserror::Error;
 
+use crate::{
+    gh_recording::{GhExchange, GhRecorder, GhReplay},
+    logging,
+};
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct GithubAuthor {
     pub login: String,
 }
 
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GithubReviewState {
+    Approved,
+    ChangesRequested,
+    Commented,
+    Dismissed,
+    Pending,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl std::fmt::Display for GithubReviewState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GithubReviewState::Approved => write!(f, "approved"),
+            GithubReviewState::ChangesRequested => write!(f, "changes requested"),
+            GithubReviewState::Commented => write!(f, "commented"),
+            GithubReviewState::Dismissed => write!(f, "dismissed"),
+            GithubReviewState::Pending => write!(f, "pending"),
+            GithubReviewState::Unknown => write!(f, "reviewed"),
+        }
+    }
+}
+
+/// Whether a pr has the approvals branch protection requires, as reported by github.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GithubReviewDecision {
+    Approved,
+    ChangesRequested,
+    ReviewRequired,
+    /// Github reports no decision for repositories without required reviews.
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+impl std::fmt::Display for GithubReviewDecision {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GithubReviewDecision::Approved => write!(f, "approved"),
+            GithubReviewDecision::ChangesRequested => write!(f, "changes requested"),
+            GithubReviewDecision::ReviewRequired => write!(f, "review required"),
+            GithubReviewDecision::Unknown => Ok(()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GithubMergeable {
+    Mergeable,
+    Conflicting,
+    /// Github computes mergeability lazily, so this is common right after a push.
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GithubMergeStateStatus {
+    Behind,
+    Blocked,
+    Clean,
+    Dirty,
+    Draft,
+    HasHooks,
+    Unstable,
+    #[default]
+    #[serde(other)]
+    Unknown,
+}
+
+#[derive(Clone, Copy, Deserialize, Serialize, Debug, PartialEq, Eq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum GithubPRState {
+    Open,
+    Closed,
+    Merged,
+}
+
+impl std::fmt::Display for GithubPRState {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GithubPRState::Open => write!(f, "open"),
+            GithubPRState::Closed => write!(f, "closed"),
+            GithubPRState::Merged => write!(f, "merged"),
+        }
+    }
+}
+
 #[derive(Clone, Deserialize, Serialize, Debug)]
 pub struct GithubPRReview {
     pub id: String,
     pub author: GithubAuthor,
     #[serde(rename = "submittedAt")]
     pub submitted_at: DateTime<Utc>,
+    #[serde(default)]
+    pub state: GithubReviewState,
+}
+
+#[derive(Clone, Copy, Serialize, Debug, Default, PartialEq, Eq)]
+pub struct ReviewCounts {
+    pub approved: usize,
+    pub changes_requested: usize,
+    pub commented: usize,
+}
+
+impl std::fmt::Display for ReviewCounts {
+    /// Compact form like `✓2 ✗1 💬3`, leaving out states with no reviews.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let parts: Vec<String> = [
+            ("✓", self.approved),
+            ("✗", self.changes_requested),
+            ("💬", self.commented),
+        ]
+        .into_iter()
+        .filter(|(_, count)| *count > 0)
+        .map(|(symbol, count)| format!("{symbol}{count}"))
+        .collect();
+
+        write!(f, "{}", parts.join(" "))
+    }
 }
 
 #[derive(Clone, Deserialize, Serialize, Debug)]
@@ -24,12 +155,130 @@ pub struct GithubPRStatus {
     pub reviews: Vec<GithubPRReview>,
     pub title: String,
     pub repository: String,
+    #[serde(default)]
+    pub created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub url: Option<String>,
+    #[serde(default)]
+    pub number: Option<u64>,
+    #[serde(default)]
+    pub review_decision: GithubReviewDecision,
+    #[serde(default)]
+    pub mergeable: GithubMergeable,
+    #[serde(default)]
+    pub merge_state_status: GithubMergeStateStatus,
+    #[serde(default)]
+    pub additions: u64,
+    #[serde(default)]
+    pub deletions: u64,
+    #[serde(default)]
+    pub changed_files: u64,
+    #[serde(default)]
+    pub labels: Vec<String>,
+    /// The branch the pr would merge into.
+    #[serde(default)]
+    pub base_branch: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<DateTime<Utc>>,
 }
 
 impl GithubPRStatus {
     pub fn latest_review_time(&self) -> Option<DateTime<Utc>> {
         self.reviews.iter().map(|r| r.submitted_at).max()
     }
+
+    /// The pr's page on github, built from the repository and number for prs stored without one.
+    pub fn web_url(&self) -> Option<String> {
+        self.url.clone().or_else(|| {
+            self.number
+                .map(|number| format!("https://github.com/{}/pull/{number}", self.repository))
+        })
+    }
+
+    pub fn latest_review(&self) -> Option<&GithubPRReview> {
+        self.reviews.iter().max_by_key(|r| r.submitted_at)
+    }
+
+    /// Counts reviews by state.
+    ///
+    /// Approvals and change requests count each reviewer's latest decisive review once, so a
+    /// reviewer who requested changes and later approved only counts as an approval. Every
+    /// comment-only review is counted.
+    pub fn review_counts(&self) -> ReviewCounts {
+        let mut reviews: Vec<&GithubPRReview> = self.reviews.iter().collect();
+        reviews.sort_by_key(|r| r.submitted_at);
+
+        let mut counts = ReviewCounts::default();
+        let mut decisions: Vec<(&str, GithubReviewState)> = Vec::new();
+        for review in reviews {
+            match review.state {
+                GithubReviewState::Commented => counts.commented += 1,
+                GithubReviewState::Approved
+                | GithubReviewState::ChangesRequested
+                | GithubReviewState::Dismissed => {
+                    decisions.retain(|(login, _)| *login != review.author.login);
+                    decisions.push((&review.author.login, review.state));
+                }
+                GithubReviewState::Pending | GithubReviewState::Unknown => {}
+            }
+        }
+
+        for (_, state) in decisions {
+            match state {
+                GithubReviewState::Approved => counts.approved += 1,
+                GithubReviewState::ChangesRequested => counts.changes_requested += 1,
+                _ => {}
+            }
+        }
+        counts
+    }
+
+    /// Whether the pr has its required approvals and is only waiting on its author to merge.
+    ///
+    /// Repositories without required reviews have no review decision, there a pr is ready once
+    /// someone approved it and nobody still requests changes.
+    pub fn is_ready_to_merge(&self) -> bool {
+        match self.review_decision {
+            GithubReviewDecision::Approved => true,
+            GithubReviewDecision::ChangesRequested | GithubReviewDecision::ReviewRequired => false,
+            GithubReviewDecision::Unknown => {
+                let counts = self.review_counts();
+                counts.approved > 0 && counts.changes_requested == 0
+            }
+        }
+    }
+
+    /// Whether the pr has `label`, ignoring case like github does.
+    pub fn has_label(&self, label: &str) -> bool {
+        self.labels
+            .iter()
+            .any(|pr_label| pr_label.eq_ignore_ascii_case(label))
+    }
+
+    /// Lines added plus lines deleted.
+    pub fn diff_size(&self) -> u64 {
+        self.additions + self.deletions
+    }
+
+    /// Whether the pr conflicts with its base branch and needs a rebase before merging.
+    pub fn has_conflicts(&self) -> bool {
+        self.mergeable == GithubMergeable::Conflicting
+            || self.merge_state_status == GithubMergeStateStatus::Dirty
+    }
+
+    /// Distinct reviewer logins in the order they first reviewed.
+    pub fn reviewers(&self) -> Vec<&str> {
+        let mut reviews: Vec<&GithubPRReview> = self.reviews.iter().collect();
+        reviews.sort_by_key(|r| r.submitted_at);
+
+        let mut reviewers: Vec<&str> = Vec::new();
+        for review in reviews {
+            if !reviewers.contains(&review.author.login.as_str()) {
+                reviewers.push(&review.author.login);
+            }
+        }
+        reviewers
+    }
 }
 
 #[derive(Error, Debug)]
@@ -52,13 +301,95 @@ pub enum GithubClientError {
         operation: String,
         underlying_error: std::io::Error,
     },
+    #[error("{operation} was not recorded")]
+    NotRecorded { operation: String },
+    #[error("Could not load gh recording {path}: {underlying_error}")]
+    InvalidRecording {
+        path: String,
+        underlying_error: Box<dyn std::error::Error + Sync + Send>,
+    },
 }
 
 #[derive(Deserialize, Serialize, Debug)]
 struct RawGithubPRStatus {
     id: String,
+    #[serde(default)]
     reviews: Vec<GithubPRReview>,
     title: String,
+    #[serde(rename = "createdAt", default)]
+    created_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    url: Option<String>,
+    #[serde(default)]
+    number: Option<u64>,
+    #[serde(rename = "reviewDecision", default)]
+    review_decision: GithubReviewDecision,
+    #[serde(default)]
+    mergeable: GithubMergeable,
+    #[serde(rename = "mergeStateStatus", default)]
+    merge_state_status: GithubMergeStateStatus,
+    #[serde(default)]
+    additions: u64,
+    #[serde(default)]
+    deletions: u64,
+    #[serde(rename = "changedFiles", default)]
+    changed_files: u64,
+    #[serde(default)]
+    labels: Vec<RawGithubLabel>,
+    #[serde(rename = "baseRefName", default)]
+    base_ref_name: Option<String>,
+    #[serde(rename = "updatedAt", default)]
+    updated_at: Option<DateTime<Utc>>,
+    /// Only asked for by incremental fetches, which include merged and closed prs.
+    #[serde(default)]
+    state: Option<GithubPRState>,
+}
+
+impl RawGithubPRStatus {
+    fn into_status(self, repository: &str) -> GithubPRStatus {
+        let RawGithubPRStatus {
+            id,
+            reviews,
+            title,
+            created_at,
+            url,
+            number,
+            review_decision,
+            mergeable,
+            merge_state_status,
+            additions,
+            deletions,
+            changed_files,
+            labels,
+            base_ref_name,
+            updated_at,
+            state: _,
+        } = self;
+
+        GithubPRStatus {
+            repository: repository.to_string(),
+            id,
+            reviews,
+            title,
+            created_at,
+            url,
+            number,
+            review_decision,
+            mergeable,
+            merge_state_status,
+            additions,
+            deletions,
+            changed_files,
+            labels: labels.into_iter().map(|label| label.name).collect(),
+            base_branch: base_ref_name,
+            updated_at,
+        }
+    }
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+struct RawGithubLabel {
+    name: String,
 }
 
 impl GithubPRStatus {
@@ -68,90 +399,501 @@ impl GithubPRStatus {
             id: self.id,
             reviews: self.reviews,
             title: self.title,
+            created_at: self.created_at,
+            url: self.url,
+            number: self.number,
+            review_decision: self.review_decision,
+            mergeable: self.mergeable,
+            merge_state_status: self.merge_state_status,
+            additions: self.additions,
+            deletions: self.deletions,
+            changed_files: self.changed_files,
+            labels: self.labels,
+            base_branch: self.base_branch,
+            updated_at: self.updated_at,
         }
     }
 }
 
+/// Github API quota of the authenticated user.
+#[derive(Clone, Copy, Deserialize, Serialize, Debug)]
+pub struct RateLimit {
+    pub limit: u64,
+    pub remaining: u64,
+    #[serde(with = "chrono::serde::ts_seconds")]
+    pub reset: DateTime<Utc>,
+}
+
+#[derive(Deserialize, Debug)]
+struct RawGithubRepository {
+    #[serde(rename = "nameWithOwner")]
+    name_with_owner: String,
+}
+
 pub type Result<T> = std::result::Result<T, GithubClientError>;
-pub struct GithubClient {}
 
-impl GithubClient {
-    pub async fn new_pr_status<S1: AsRef<str>, S2: AsRef<str>>(
+/// Fields `gh pr list` is asked for to build a `GithubPRStatus`.
+const PR_FIELDS: &str = "id,title,reviews,createdAt,url,number,reviewDecision,mergeable,mergeStateStatus,additions,deletions,changedFiles,labels,baseRefName,updatedAt";
+/// Incremental fetches should only see a handful of prs, this leaves plenty of headroom over
+/// gh's default of 30. Hitting it makes the session fall back to a full fetch.
+const UPDATED_PRS_LIMIT: usize = 100;
+
+/// A source of pull requests for a session, implemented by `GithubClient` through the gh cli.
+/// Implement it to feed a session from fixtures or another forge.
+pub trait PrFetcher {
+    /// Open prs in `repository` (`owner/name`) authored by `author`.
+    fn fetch_prs(
         &self,
-        repository: S1,
-        author: Option<S2>,
-    ) -> Result<Vec<GithubPRStatus>> {
-        let repository = repository.as_ref();
-        let mut command = {
-            let mut c = Command::new("gh");
-            c.arg("pr").arg("list").arg("--repo").arg(repository);
+        repository: &str,
+        author: &str,
+    ) -> impl Future<Output = Result<Vec<GithubPRStatus>>> + Send;
 
-            if let Some(author) = author {
-                c.arg("--author").arg(author.as_ref());
-            }
-            c.arg("--json")
-                .arg("id,title,reviews")
-                .stdout(Stdio::null())
-                .stderr(Stdio::null());
-            c
-        };
+    /// Whether pr `number` in `repository` is still open, merged or closed.
+    fn fetch_pr_state(
+        &self,
+        repository: &str,
+        number: u64,
+    ) -> impl Future<Output = Result<GithubPRState>> + Send;
+
+    /// Prs in `repository` by `author` updated at or after `since`, including merged and closed
+    /// ones. `None` means the fetcher can't filter by update time, so sessions fall back to
+    /// fetching every open pr on each refresh.
+    fn fetch_updated_prs(
+        &self,
+        repository: &str,
+        author: &str,
+        since: DateTime<Utc>,
+    ) -> impl Future<Output = Result<Option<Vec<(GithubPRStatus, GithubPRState)>>>> + Send {
+        let _ = (repository, author, since);
+        async { Ok(None) }
+    }
+}
+
+/// Runs the gh cli, optionally recording what it prints or replaying an earlier recording
+/// instead. Setting `GHPRS_GH_RECORD` or `GHPRS_GH_REPLAY` to a path does the same for clients
+/// made with `new`.
+pub struct GithubClient {
+    mode: GhMode,
+}
+
+enum GhMode {
+    Live,
+    Record(GhRecorder),
+    Replay(GhReplay),
+}
+
+impl PrFetcher for GithubClient {
+    fn fetch_prs(
+        &self,
+        repository: &str,
+        author: &str,
+    ) -> impl Future<Output = Result<Vec<GithubPRStatus>>> + Send {
+        self.new_pr_status(repository, Some(author))
+    }
+
+    fn fetch_pr_state(
+        &self,
+        repository: &str,
+        number: u64,
+    ) -> impl Future<Output = Result<GithubPRState>> + Send {
+        self.pr_state(repository, number)
+    }
+
+    async fn fetch_updated_prs(
+        &self,
+        repository: &str,
+        author: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Option<Vec<(GithubPRStatus, GithubPRState)>>> {
+        // A full page may have cut off older updates, only a full fetch sees all of them.
+        let prs = self.updated_pr_statuses(repository, author, since).await?;
+        Ok((prs.len() < UPDATED_PRS_LIMIT).then_some(prs))
+    }
+}
+
+impl GithubClient {
+    /// Runs gh with `args`, or answers from the recording when replaying.
+    async fn run_gh(&self, args: &[&str]) -> Result<GhExchange> {
+        let operation = format!("gh {}", args.join(" "));
+        let args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
 
-        let command_output = match command.output().await {
+        if let GhMode::Replay(replay) = &self.mode {
+            logging::debug!("replayed gh", command = operation);
+            return replay.replay(&args);
+        }
+
+        let started = Instant::now();
+        let command_output = match Command::new("gh").args(&args).output().await {
             Ok(command_output) => command_output,
             Err(e) => {
                 return Err(GithubClientError::UnexpectedCommandError {
-                    operation: "gh pr list".to_string(),
+                    operation,
                     underlying_error: e,
                 })
             }
         };
 
-        let pr_json = String::from_utf8_lossy(&command_output.stdout).to_string();
+        logging::debug!(
+            "ran gh",
+            command = operation,
+            status = command_output.status,
+            duration_ms = started.elapsed().as_millis()
+        );
+
+        let exchange = GhExchange {
+            args,
+            status: command_output.status.code(),
+            stdout: String::from_utf8_lossy(&command_output.stdout).to_string(),
+            stderr: String::from_utf8_lossy(&command_output.stderr).to_string(),
+        };
+        if let GhMode::Record(recorder) = &self.mode {
+            if let Err(e) = recorder.record(&exchange) {
+                logging::warn!(
+                    "failed to record gh output",
+                    path = recorder.path().display(),
+                    error = e
+                );
+            }
+        }
+
+        Ok(exchange)
+    }
+
+    async fn gh_json<T: DeserializeOwned>(&self, args: &[&str]) -> Result<T> {
+        let exchange = self.run_gh(args).await?;
+
+        serde_json::from_str(&exchange.stdout).map_err(|e| GithubClientError::UnexpectedOutput {
+            operation: format!("gh {}", args.join(" ")),
+            stderr: exchange.stderr,
+            stdout: exchange.stdout,
+            underlying_error: Box::new(e),
+        })
+    }
+
+    /// The login of the user the gh cli is authenticated as.
+    pub async fn authenticated_login(&self) -> Result<String> {
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+
+        let user: User = self.gh_json(&["api", "user"]).await?;
+        Ok(user.login)
+    }
 
-        let raw_pr_statuses: Vec<RawGithubPRStatus> =
-            serde_json::from_str(&pr_json).map_err(|e| GithubClientError::UnexpectedOutput {
-                operation: "gh pr list".to_string(),
-                stderr: String::from_utf8_lossy(&command_output.stderr).to_string(),
-                stdout: String::from_utf8_lossy(&command_output.stdout).to_string(),
-                underlying_error: Box::new(e),
-            })?;
+    /// Repositories owned by the authenticated user, as `owner/name`.
+    pub async fn list_repositories(&self) -> Result<Vec<String>> {
+        let repositories: Vec<RawGithubRepository> = self
+            .gh_json(&["repo", "list", "--limit", "100", "--json", "nameWithOwner"])
+            .await?;
 
-        Ok(raw_pr_statuses
+        Ok(repositories
             .into_iter()
-            .map(|raw| {
-                let RawGithubPRStatus { id, reviews, title } = raw;
+            .map(|repository| repository.name_with_owner)
+            .collect())
+    }
 
-                GithubPRStatus {
-                    repository: repository.to_string(),
-                    id,
-                    reviews,
-                    title,
-                }
+    /// The GraphQL quota, which is what `gh pr list` draws from.
+    pub async fn rate_limit(&self) -> Result<RateLimit> {
+        #[derive(Deserialize)]
+        struct Resources {
+            graphql: RateLimit,
+        }
+        #[derive(Deserialize)]
+        struct RateLimits {
+            resources: Resources,
+        }
+
+        let rate_limits: RateLimits = self.gh_json(&["api", "rate_limit"]).await?;
+        Ok(rate_limits.resources.graphql)
+    }
+
+    /// Looks up a user by login, failing if there is no such user.
+    pub async fn view_user(&self, login: &str) -> Result<String> {
+        #[derive(Deserialize)]
+        struct User {
+            login: String,
+        }
+
+        let user: User = self.gh_json(&["api", &format!("users/{login}")]).await?;
+        Ok(user.login)
+    }
+
+    /// Looks up a repository, failing if it doesn't exist or isn't accessible.
+    pub async fn view_repository(&self, repository: &str) -> Result<String> {
+        let repository: RawGithubRepository = self
+            .gh_json(&["repo", "view", repository, "--json", "nameWithOwner"])
+            .await?;
+
+        Ok(repository.name_with_owner)
+    }
+
+    /// Whether a pr is still open, merged or closed.
+    pub async fn pr_state(&self, repository: &str, number: u64) -> Result<GithubPRState> {
+        #[derive(Deserialize)]
+        struct Pr {
+            state: GithubPRState,
+        }
+
+        let pr: Pr = self
+            .gh_json(&[
+                "pr",
+                "view",
+                &number.to_string(),
+                "--repo",
+                repository,
+                "--json",
+                "state",
+            ])
+            .await?;
+        Ok(pr.state)
+    }
+
+    /// The repository of the git remote in the current directory, if there is one.
+    pub async fn current_repository(&self) -> Option<String> {
+        let repository: RawGithubRepository = self
+            .gh_json(&["repo", "view", "--json", "nameWithOwner"])
+            .await
+            .ok()?;
+
+        Some(repository.name_with_owner)
+    }
+
+    /// Runs `gh pr list` for `repository` with `args`, asking for every field `GithubPRStatus` needs
+    /// plus `extra_fields`.
+    async fn list_prs(
+        &self,
+        repository: &str,
+        args: &[&str],
+        extra_fields: &str,
+    ) -> Result<Vec<RawGithubPRStatus>> {
+        let fields = format!("{PR_FIELDS}{extra_fields}");
+        let mut command_args = vec!["pr", "list", "--repo", repository];
+        command_args.extend_from_slice(args);
+        command_args.extend_from_slice(&["--json", &fields]);
+
+        self.gh_json(&command_args).await
+    }
+
+    pub async fn new_pr_status<S1: AsRef<str>, S2: AsRef<str>>(
+        &self,
+        repository: S1,
+        author: Option<S2>,
+    ) -> Result<Vec<GithubPRStatus>> {
+        let repository = repository.as_ref();
+        let args = match &author {
+            Some(author) => vec!["--author", author.as_ref()],
+            None => vec![],
+        };
+
+        Ok(self
+            .list_prs(repository, &args, "")
+            .await?
+            .into_iter()
+            .map(|raw| raw.into_status(repository))
+            .collect())
+    }
+
+    /// Prs by `author` in `repository` updated at or after `since`, whether open, merged or closed.
+    pub async fn updated_pr_statuses(
+        &self,
+        repository: &str,
+        author: &str,
+        since: DateTime<Utc>,
+    ) -> Result<Vec<(GithubPRStatus, GithubPRState)>> {
+        let search = format!("updated:>={}", since.format("%Y-%m-%dT%H:%M:%SZ"));
+        let limit = UPDATED_PRS_LIMIT.to_string();
+        let args = [
+            "--author", author, "--state", "all", "--limit", &limit, "--search", &search,
+        ];
+
+        Ok(self
+            .list_prs(repository, &args, ",state")
+            .await?
+            .into_iter()
+            .map(|raw| {
+                let state = raw.state.unwrap_or(GithubPRState::Open);
+                (raw.into_status(repository), state)
             })
             .collect())
     }
 
+    /// A client for the authenticated gh cli, recording or replaying when `GHPRS_GH_RECORD` or
+    /// `GHPRS_GH_REPLAY` is set.
     pub async fn new() -> Result<GithubClient> {
-        match Command::new("gh")
-            .arg("auth")
-            .arg("status")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
-            .await
-        {
-            Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
-                Err(GithubClientError::CannotFindGithubCLI)
-            }
-            Err(e) => {
-                panic!("Got unexpected error checking gh auth status: {e}");
+        if let Some(path) = env_path("GHPRS_GH_REPLAY") {
+            return GithubClient::replaying(&path);
+        }
+        match env_path("GHPRS_GH_RECORD") {
+            Some(path) => GithubClient::recording(path).await,
+            None => {
+                check_gh_auth().await?;
+                Ok(GithubClient { mode: GhMode::Live })
             }
-            Ok(status) => match status.code() {
-                Some(0) => Ok(GithubClient {}),
-                Some(1) => Err(GithubClientError::NotLoggedIn),
-                Some(code) => panic!("Got unexpected status code checking gh auth status: {code}"),
-                None => panic!("Unexpectedly got no status code checking gh auth status"),
-            },
         }
     }
+
+    /// A client that runs gh and appends every invocation and its output to `path` as a line
+    /// of JSON.
+    pub async fn recording(path: PathBuf) -> Result<GithubClient> {
+        check_gh_auth().await?;
+        Ok(GithubClient {
+            mode: GhMode::Record(GhRecorder::new(path)),
+        })
+    }
+
+    /// A client that never runs gh, answering from a recording made by `recording` instead.
+    /// Invocations missing from the recording fail with `NotRecorded`.
+    pub fn replaying(path: &Path) -> Result<GithubClient> {
+        Ok(GithubClient {
+            mode: GhMode::Replay(GhReplay::load(path)?),
+        })
+    }
+}
+
+fn env_path(name: &str) -> Option<PathBuf> {
+    env::var_os(name)
+        .filter(|path| !path.is_empty())
+        .map(PathBuf::from)
+}
+
+/// Fails unless gh is installed and logged in.
+async fn check_gh_auth() -> Result<()> {
+    match Command::new("gh")
+        .arg("auth")
+        .arg("status")
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .await
+    {
+        Err(ref e) if e.kind() == std::io::ErrorKind::NotFound => {
+            Err(GithubClientError::CannotFindGithubCLI)
+        }
+        Err(e) => {
+            panic!("Got unexpected error checking gh auth status: {e}");
+        }
+        Ok(status) => match status.code() {
+            Some(0) => Ok(()),
+            Some(1) => Err(GithubClientError::NotLoggedIn),
+            Some(code) => panic!("Got unexpected status code checking gh auth status: {code}"),
+            None => panic!("Unexpectedly got no status code checking gh auth status"),
+        },
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gh_recording::GhExchange;
+    use serde_json::{json, Value};
+
+    fn recording(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/recordings")
+            .join(name)
+    }
+
+    fn replaying(name: &str) -> GithubClient {
+        GithubClient::replaying(&recording(name)).unwrap()
+    }
+
+    #[test]
+    fn replays_prs_without_reviews() {
+        let client = replaying("empty_reviews.jsonl");
+
+        let prs = smol::block_on(client.fetch_prs("org/a", "me")).unwrap();
+        assert_eq!(prs.len(), 1);
+        assert!(prs[0].reviews.is_empty());
+        assert_eq!(prs[0].latest_review_time(), None);
+        assert_eq!(prs[0].repository, "org/a");
+        assert_eq!(prs[0].base_branch.as_deref(), Some("main"));
+
+        let prs = smol::block_on(client.fetch_prs("org/b", "me")).unwrap();
+        assert!(prs.is_empty());
+    }
+
+    #[test]
+    fn replays_prs_missing_optional_fields() {
+        let client = replaying("missing_fields.jsonl");
+
+        let prs = smol::block_on(client.fetch_prs("org/a", "me")).unwrap();
+        assert_eq!(prs.len(), 1);
+        let pr = &prs[0];
+        assert_eq!(pr.title, "Sparse pr");
+        assert_eq!(pr.reviews.len(), 1);
+        assert_eq!(pr.created_at, None);
+        assert_eq!(pr.url, None);
+        assert_eq!(pr.number, None);
+        assert_eq!(pr.base_branch, None);
+        assert_eq!(pr.updated_at, None);
+        assert_eq!(pr.web_url(), None);
+    }
+
+    #[test]
+    fn replays_huge_payloads() {
+        let reviews: Vec<Value> = (0..50)
+            .map(|review| {
+                json!({
+                    "id": format!("R{review}"),
+                    "author": { "login": format!("reviewer{review}") },
+                    "submittedAt": "2026-10-03T00:00:00Z",
+                    "state": "COMMENTED",
+                })
+            })
+            .collect();
+        let prs: Vec<Value> = (0..2000)
+            .map(|number| {
+                json!({
+                    "id": format!("PR_{number}"),
+                    "title": "x".repeat(200),
+                    "reviews": reviews,
+                    "number": number,
+                    "labels": [{ "name": "backend" }],
+                })
+            })
+            .collect();
+        let exchange = GhExchange {
+            args: [
+                "pr", "list", "--repo", "org/a", "--author", "me", "--json", PR_FIELDS,
+            ]
+            .map(str::to_string)
+            .to_vec(),
+            status: Some(0),
+            stdout: serde_json::to_string(&prs).unwrap(),
+            stderr: String::new(),
+        };
+        let path = std::env::temp_dir().join(format!("ghprs-huge-{}.jsonl", std::process::id()));
+        std::fs::write(&path, serde_json::to_string(&exchange).unwrap()).unwrap();
+
+        let client = GithubClient::replaying(&path).unwrap();
+        let fetched = smol::block_on(client.fetch_prs("org/a", "me"));
+        std::fs::remove_file(&path).unwrap();
+
+        let fetched = fetched.unwrap();
+        assert_eq!(fetched.len(), 2000);
+        assert!(fetched.iter().all(|pr| pr.reviews.len() == 50));
+        assert_eq!(fetched[1999].number, Some(1999));
+        assert_eq!(fetched[0].labels, vec!["backend".to_string()]);
+    }
+
+    #[test]
+    fn replays_incremental_fetches_from_any_watermark() {
+        let client = replaying("incremental.jsonl");
+        let since = "2026-10-14T12:34:56Z".parse().unwrap();
+
+        let updates = smol::block_on(client.fetch_updated_prs("org/a", "me", since))
+            .unwrap()
+            .unwrap();
+        assert_eq!(updates.len(), 1);
+        assert_eq!(updates[0].1, GithubPRState::Merged);
+    }
+
+    #[test]
+    fn unrecorded_invocations_fail() {
+        let client = replaying("empty_reviews.jsonl");
+
+        let result = smol::block_on(client.fetch_prs("org/c", "me"));
+        assert!(matches!(result, Err(GithubClientError::NotRecorded { .. })));
+    }
 }
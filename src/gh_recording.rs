@@ -0,0 +1,118 @@
+use std::{
+    collections::HashMap,
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+    sync::{Mutex, PoisonError},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::gh_client::{GithubClientError, Result};
+
+/// A gh invocation and what it printed, recordings hold one per line.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct GhExchange {
+    pub args: Vec<String>,
+    /// Exit code, `None` if gh was killed by a signal.
+    pub status: Option<i32>,
+    pub stdout: String,
+    pub stderr: String,
+}
+
+/// Appends every exchange to a recording as gh runs.
+pub(crate) struct GhRecorder {
+    path: PathBuf,
+    // Concurrent fetches finish in any order, keep their lines from interleaving
+    lock: Mutex<()>,
+}
+
+impl GhRecorder {
+    pub(crate) fn new(path: PathBuf) -> GhRecorder {
+        GhRecorder {
+            path,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn record(&self, exchange: &GhExchange) -> std::io::Result<()> {
+        let mut line = serde_json::to_string(exchange)?;
+        line.push('\n');
+
+        let _lock = self.lock.lock().unwrap_or_else(PoisonError::into_inner);
+        OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)?
+            .write_all(line.as_bytes())
+    }
+}
+
+/// Prefix of the search incremental fetches narrow `gh pr list` with, followed by a watermark.
+const UPDATED_SEARCH_PREFIX: &str = "updated:>=";
+
+/// The args an exchange is looked up by. Watermarks move on every fetch, so an incremental fetch
+/// matches a recorded one whatever time it asks from.
+fn replay_key(args: &[String]) -> Vec<String> {
+    args.iter()
+        .map(|arg| match arg.strip_prefix(UPDATED_SEARCH_PREFIX) {
+            Some(_) => format!("{UPDATED_SEARCH_PREFIX}*"),
+            None => arg.clone(),
+        })
+        .collect()
+}
+
+/// Answers gh invocations from a recording instead of running gh. An invocation recorded several
+/// times is answered in the recorded order, repeating the last answer once they run out.
+pub(crate) struct GhReplay {
+    exchanges: HashMap<Vec<String>, Vec<GhExchange>>,
+    replayed: Mutex<HashMap<Vec<String>, usize>>,
+}
+
+impl GhReplay {
+    pub(crate) fn load(path: &Path) -> Result<GhReplay> {
+        let invalid_recording = |underlying_error: Box<dyn std::error::Error + Sync + Send>| {
+            GithubClientError::InvalidRecording {
+                path: path.display().to_string(),
+                underlying_error,
+            }
+        };
+
+        let contents = std::fs::read_to_string(path).map_err(|e| invalid_recording(Box::new(e)))?;
+        let mut exchanges: HashMap<Vec<String>, Vec<GhExchange>> = HashMap::new();
+        for line in contents.lines().filter(|line| !line.trim().is_empty()) {
+            let exchange: GhExchange =
+                serde_json::from_str(line).map_err(|e| invalid_recording(Box::new(e)))?;
+            exchanges
+                .entry(replay_key(&exchange.args))
+                .or_default()
+                .push(exchange);
+        }
+
+        Ok(GhReplay {
+            exchanges,
+            replayed: Mutex::new(HashMap::new()),
+        })
+    }
+
+    pub(crate) fn replay(&self, args: &[String]) -> Result<GhExchange> {
+        let key = replay_key(args);
+        let exchanges = self
+            .exchanges
+            .get(&key)
+            .ok_or_else(|| GithubClientError::NotRecorded {
+                operation: format!("gh {}", args.join(" ")),
+            })?;
+
+        let mut replayed = self.replayed.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = replayed.entry(key).or_default();
+        let exchange = &exchanges[(*count).min(exchanges.len() - 1)];
+        *count += 1;
+
+        Ok(exchange.clone())
+    }
+}
@@ -0,0 +1,129 @@
+use std::{
+    collections::{HashMap, HashSet},
+    io::{self, Write},
+    path::Path,
+};
+
+use anyhow::bail;
+
+use crate::{
+    gh_client::GithubClient,
+    prs::{SessionConfig, SessionState},
+};
+
+fn prompt(question: &str) -> anyhow::Result<String> {
+    print!("{question}");
+    io::stdout().flush()?;
+
+    let mut buffer = String::new();
+    if io::stdin().read_line(&mut buffer)? == 0 {
+        bail!("Aborted init, no input")
+    }
+    Ok(buffer.trim().to_string())
+}
+
+/// Resolves a comma separated answer of indices into `candidates` or literal `owner/name`s.
+fn parse_repository_selection(
+    answer: &str,
+    candidates: &[String],
+) -> anyhow::Result<HashSet<String>> {
+    answer
+        .split(',')
+        .map(str::trim)
+        .filter(|selection| !selection.is_empty())
+        .map(|selection| match str::parse::<usize>(selection) {
+            Ok(index) => match candidates.get(index) {
+                Some(repository) => Ok(repository.clone()),
+                None => bail!("Invalid index {index}"),
+            },
+            Err(_) if selection.contains('/') => Ok(selection.to_string()),
+            Err(_) => bail!("Expected an index or owner/name, got '{selection}'"),
+        })
+        .collect()
+}
+
+/// Walks through creating a config file and an empty state file. An existing state file is only
+/// reset if confirmed, it holds every ack, snooze, note and the history.
+pub async fn run_init(config_path: &Path, state_path: &Path) -> anyhow::Result<()> {
+    if config_path.exists() {
+        let answer = prompt(&format!(
+            "{} already exists, overwrite it? [y/N]: ",
+            config_path.display()
+        ))?;
+        if !answer.eq_ignore_ascii_case("y") {
+            bail!("Aborted init, config file left untouched")
+        }
+    }
+
+    let reset_state = !state_path.exists() || {
+        let answer = prompt(&format!(
+            "{} already exists, reset it and lose all acks, snoozes, notes and history? [y/N]: ",
+            state_path.display()
+        ))?;
+        answer.eq_ignore_ascii_case("y")
+    };
+
+    let github_client = GithubClient::new().await?;
+
+    let login = github_client.authenticated_login().await?;
+    let author = match prompt(&format!("Author to track [{login}]: "))? {
+        author if author.is_empty() => login,
+        author => author,
+    };
+
+    let current_repository = github_client.current_repository().await;
+    let mut candidates: Vec<String> = current_repository.iter().cloned().collect();
+    match github_client.list_repositories().await {
+        Ok(repositories) => candidates.extend(
+            repositories
+                .into_iter()
+                .filter(|repository| Some(repository) != current_repository.as_ref()),
+        ),
+        Err(e) => eprintln!("Could not list repositories: {e}"),
+    }
+
+    for (index, repository) in candidates.iter().enumerate() {
+        println!("{index:>3}  {repository}");
+    }
+
+    let repositories = loop {
+        let answer = prompt(
+            "Repositories to track, comma separated indices or owner/name (blank for the first): ",
+        )?;
+        let answer = if answer.is_empty() { "0" } else { &answer };
+
+        match parse_repository_selection(answer, &candidates) {
+            Ok(repositories) if !repositories.is_empty() => break repositories,
+            Ok(_) => eprintln!(">> ERROR: Select at least one repository"),
+            Err(e) => eprintln!(">> ERROR: {e}"),
+        }
+    };
+
+    let session_config = SessionConfig {
+        author,
+        repositories,
+        repository_authors: HashMap::new(),
+        ack_ttl: None,
+        stale_after: None,
+        required_reviewers: None,
+        base_branch: None,
+        archive_retention: None,
+    };
+
+    for path in [config_path, state_path] {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+    }
+    std::fs::write(config_path, toml::to_string(&session_config)?)?;
+    println!("Wrote config to {}", config_path.display());
+
+    if reset_state {
+        crate::save_session_state(&SessionState::default(), state_path)?;
+        println!("Wrote empty session state to {}", state_path.display());
+    } else {
+        println!("Kept session state {}", state_path.display());
+    }
+
+    Ok(())
+}
@@ -0,0 +1,40 @@
+//! Tracks acknowledgement of reviews on GitHub pull requests.
+//!
+//! A [`Session`] holds the prs of the configured author across repositories along with which
+//! reviews have been acknowledged. It is split into a [`SessionConfig`], which comes from the
+//! user's config file, and a [`SessionState`], which is persisted between runs. Use
+//! [`migrations::parse_state`] to load state written by any version of ghprs.
+//!
+//! Prs are fetched through a [`PrFetcher`], [`GithubClient`] is the implementation backed by the
+//! gh cli and [`Fixture`] serves prs recorded earlier. The free functions in [`prs`] acknowledge,
+//! snooze and list prs, refetching first when the session is more than five minutes stale.
+//!
+//! # Stability
+//!
+//! Items re-exported from the crate root follow semver, which before 1.0 means breaking changes
+//! only come with a minor version bump. The layout of [`SessionState`] is versioned separately,
+//! see [`migrations::STATE_VERSION`]. Everything else, including `logging`, exists for the ghp
+//! binary and may change in any release.
+
+pub mod fixtures;
+pub mod gh_client;
+pub mod gh_recording;
+#[doc(hidden)]
+pub mod logging;
+pub mod migrations;
+pub mod prs;
+
+pub use fixtures::Fixture;
+pub use gh_client::{
+    GithubClient, GithubClientError, GithubMergeStateStatus, GithubMergeable, GithubPRReview,
+    GithubPRState, GithubPRStatus, GithubReviewDecision, GithubReviewState, PrFetcher, RateLimit,
+    ReviewCounts,
+};
+pub use gh_recording::GhExchange;
+pub use prs::{
+    acknowledge_review, acknowledged_prs, archived_prs, cached_unacknowledged_prs,
+    preview_acknowledgement, ready_to_merge_prs, snoozed_prs, stale_prs,
+    take_unreported_archived_prs, unacknowledge_review, unacknowledged_prs, unsnooze_review,
+    AcknowledgementPreview, ArchivedPr, FetchWatermark, PrFilter, PullRequestId, Session,
+    SessionConfig, SessionError, SessionPr, SessionState,
+};
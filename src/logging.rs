@@ -0,0 +1,135 @@
+use std::{env, sync::OnceLock};
+
+use chrono::Utc;
+use serde_json::{Map, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "error",
+            Level::Warn => "warn",
+            Level::Info => "info",
+            Level::Debug => "debug",
+        }
+    }
+
+    fn parse(s: &str) -> Option<Level> {
+        [Level::Error, Level::Warn, Level::Info, Level::Debug]
+            .into_iter()
+            .find(|level| level.name().eq_ignore_ascii_case(s))
+    }
+}
+
+struct Logger {
+    max_level: Level,
+    json: bool,
+}
+
+static LOGGER: OnceLock<Logger> = OnceLock::new();
+
+/// Emits events up to `max_level` if given, else up to the level in `GHPRS_LOG`, defaulting to warn.
+/// `GHPRS_LOG_FORMAT=json` emits one JSON object per line instead of text.
+pub fn init(max_level: Option<Level>) {
+    let max_level = max_level
+        .or_else(|| {
+            env::var("GHPRS_LOG")
+                .ok()
+                .and_then(|level| Level::parse(&level))
+        })
+        .unwrap_or(Level::Warn);
+    let json = env::var("GHPRS_LOG_FORMAT").is_ok_and(|format| format == "json");
+
+    let _ = LOGGER.set(Logger { max_level, json });
+}
+
+fn logger() -> &'static Logger {
+    LOGGER.get_or_init(|| Logger {
+        max_level: Level::Warn,
+        json: false,
+    })
+}
+
+fn text_value(value: &str) -> String {
+    if value.is_empty() || value.contains(char::is_whitespace) || value.contains('"') {
+        format!("{value:?}")
+    } else {
+        value.to_string()
+    }
+}
+
+/// Writes an event to stderr if `level` is enabled, use the level macros rather than calling this.
+pub fn emit(level: Level, message: &str, fields: &[(&str, String)]) {
+    let logger = logger();
+    if level > logger.max_level {
+        return;
+    }
+
+    if logger.json {
+        let mut event = Map::new();
+        event.insert("time".to_string(), Utc::now().to_rfc3339().into());
+        event.insert("level".to_string(), level.name().into());
+        event.insert("message".to_string(), message.into());
+        for (key, value) in fields {
+            event.insert(key.to_string(), value.clone().into());
+        }
+        eprintln!("{}", Value::Object(event));
+    } else {
+        let mut line = format!("{} {message}", level.name().to_uppercase());
+        for (key, value) in fields {
+            line += &format!(" {key}={}", text_value(value));
+        }
+        eprintln!("{line}");
+    }
+}
+
+/// Logs a message with `key = value` fields, values only need to implement `Display`.
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __logging_event {
+    ($level:expr, $message:expr $(, $key:ident = $value:expr)* $(,)?) => {
+        $crate::logging::emit(
+            $level,
+            $message,
+            &[$((stringify!($key), $value.to_string())),*],
+        )
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __logging_error {
+    ($($arg:tt)*) => { $crate::logging::event!($crate::logging::Level::Error, $($arg)*) };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __logging_warn {
+    ($($arg:tt)*) => { $crate::logging::event!($crate::logging::Level::Warn, $($arg)*) };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __logging_info {
+    ($($arg:tt)*) => { $crate::logging::event!($crate::logging::Level::Info, $($arg)*) };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __logging_debug {
+    ($($arg:tt)*) => { $crate::logging::event!($crate::logging::Level::Debug, $($arg)*) };
+}
+
+// Exported under prefixed names so they are only reachable as `logging::warn!` and friends,
+// a bare `warn` would also clash with the builtin attribute.
+pub use crate::{
+    __logging_debug as debug, __logging_error as error, __logging_event as event,
+    __logging_info as info, __logging_warn as warn,
+};
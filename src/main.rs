@@ -1,24 +1,54 @@
-mod gh_client;
-mod prs;
+mod clipboard;
+mod color;
+mod completions;
+mod config;
+mod dirs;
+mod doctor;
+mod duration;
+mod export;
+mod fuzzy;
+mod init;
+mod man;
+mod pager;
+mod serve;
+mod stats;
 
+use ghprs::{gh_client, logging, migrations, prs, Fixture};
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, HashMap, HashSet},
     env,
+    fs::TryLockError,
     io::{self, Read, Write},
+    ops::{Deref, DerefMut},
     path::{Path, PathBuf},
+    process::ExitCode,
+    time::{Duration, Instant},
 };
 
-use anyhow::bail;
-use chrono::{DateTime, Local};
-use clap::{Parser, Subcommand};
-use gh_client::GithubPRStatus;
+use anyhow::{anyhow, bail};
+use chrono::{DateTime, Local, Utc};
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use color::{paint_table_rows, ColorChoice, Style};
+use config::RepoCommand;
+use dirs::{config_directory, legacy_state_directory, state_directory};
+use export::{delimited, markdown, pr_record, Delimiter, PR_RECORD_HEADER};
+use gh_client::{
+    GithubClient, GithubPRState, GithubPRStatus, GithubReviewDecision, GithubReviewState,
+    ReviewCounts,
+};
 use prs::{
-    acknowledge_review, clear_session, unacknowledge_review, unacknowledged_prs, Session,
-    SessionConfig, SessionState,
+    acknowledge_review, archived_prs, cached_unacknowledged_prs, clear_session, ignored_prs,
+    mute_repository, preview_acknowledgement, ready_to_merge_prs, set_ignored, snooze_review,
+    stale_prs, take_unreported_archived_prs, unacknowledge_review, unacknowledged_prs,
+    undo_last_operation, unmute_repository, AcknowledgementPreview, ArchivedPr, HistoryEntry,
+    PrFilter, PullRequestId, Session, SessionConfig, SessionState,
 };
 use serde::{Deserialize, Serialize};
 use serde_json::json;
-use tabled::{Table, Tabled};
+use tabled::{
+    settings::{locator::ByColumnName, Disable},
+    Table, Tabled,
+};
 
 use crate::prs::acknowledged_prs;
 
@@ -31,23 +61,364 @@ enum Command {
     Count {
         #[arg(long)]
         json: bool,
+        #[arg(long, help = "exit with status 1 when there are unacknowledged prs")]
+        check: bool,
+        #[arg(long, help = "break the count down per repository")]
+        by_repo: bool,
+        #[command(flatten)]
+        filter: FilterArgs,
+    },
+    #[clap(
+        about = "prints a compact summary of cached unacknowledged prs for status bars, never contacts github"
+    )]
+    Status {
+        #[arg(long, value_enum, default_value_t = StatusFormat::Plain)]
+        format: StatusFormat,
+        #[arg(long, conflicts_with = "format", help = "shorthand for --format tmux")]
+        tmux: bool,
     },
     #[clap(alias = "f", about = "lists unacknowledged prs; aliased to 'f'")]
     Fetch {
-        #[arg(long)]
+        #[arg(long, conflicts_with = "format", help = "shorthand for --format json")]
         json: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "json"],
+            help = "stable tab separated output for scripts: id, repository, number, latest review time"
+        )]
+        porcelain: bool,
+        #[arg(
+            long,
+            help = "list prs that have their required approvals instead, acknowledged or not"
+        )]
+        ready_to_merge: bool,
+        #[arg(
+            long,
+            help = "also print how many tracked prs were merged or closed since the last check"
+        )]
+        outcomes: bool,
+        #[arg(
+            long,
+            value_parser = duration::parse_since,
+            help = "only prs whose newest review is within this window, e.g. 24h or 2024-01-01"
+        )]
+        since: Option<DateTime<Utc>>,
+        #[command(flatten)]
+        filter: FilterArgs,
+        #[command(flatten)]
+        ad_hoc: AdHocArgs,
     },
     #[clap(alias = "fa", about = "lists acknowledged prs; aliased to 'fa'")]
     FetchAcked {
-        #[arg(long)]
+        #[arg(long, conflicts_with = "format", help = "shorthand for --format json")]
         json: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        #[arg(
+            long,
+            conflicts_with_all = ["format", "json"],
+            help = "stable tab separated output for scripts: id, repository, number, latest review time"
+        )]
+        porcelain: bool,
     },
     #[clap(alias = "a", about = "acknowledge a review; aliased to 'a'")]
-    Ack {},
+    Ack {
+        #[arg(long, help = "note to store alongside the acknowledgement")]
+        note: Option<String>,
+        #[arg(
+            long,
+            help = "print which pr and reviews would be hidden without saving anything"
+        )]
+        dry_run: bool,
+        #[arg(
+            long,
+            help = "acknowledge the pr ids read from stdin, one per line, such as fetch --porcelain output"
+        )]
+        stdin: bool,
+    },
     #[clap(alias = "ua", about = "unacknowledge a review; aliased to 'ua'")]
-    Unack {},
+    Unack {
+        #[arg(
+            long,
+            help = "print which pr and reviews would be listed again without saving anything"
+        )]
+        dry_run: bool,
+    },
+    #[clap(
+        about = "lists unacknowledged prs whose newest review is older than a duration, oldest first"
+    )]
+    Stale {
+        #[arg(long, value_parser = duration::parse_duration, help = "minimum age of the newest review, e.g. 3d, defaults to the stale_after config key")]
+        older_than: Option<chrono::Duration>,
+        #[arg(long)]
+        json: bool,
+    },
+    #[clap(
+        alias = "sn",
+        about = "hide an unacknowledged pr until a duration passes or a new review arrives; aliased to 'sn'"
+    )]
+    Snooze {
+        #[arg(help = "index of the pr in the unacknowledged listing, prompts if omitted")]
+        index: Option<usize>,
+        #[arg(long = "for", value_parser = duration::parse_duration, help = "how long to snooze for, e.g. 30m, 2h, 7d")]
+        duration: chrono::Duration,
+    },
+    #[clap(
+        alias = "i",
+        about = "permanently hide a pr from the ack and unack listings; aliased to 'i'"
+    )]
+    Ignore {
+        #[arg(help = "index in the unacknowledged listing or pr id, prompts if omitted")]
+        pr: Option<String>,
+    },
+    #[clap(alias = "il", about = "lists ignored prs; aliased to 'il'")]
+    Ignored {
+        #[arg(long)]
+        json: bool,
+    },
+    #[clap(
+        alias = "ar",
+        about = "lists merged and closed prs with their final acknowledgement; aliased to 'ar'"
+    )]
+    Archived {
+        #[arg(long)]
+        json: bool,
+    },
+    #[clap(alias = "ui", about = "stop ignoring a pr; aliased to 'ui'")]
+    Unignore {
+        #[arg(help = "index in the ignored listing or pr id, prompts if omitted")]
+        pr: Option<String>,
+    },
+    #[clap(
+        alias = "m",
+        about = "hide all prs from a repository for a duration; aliased to 'm'"
+    )]
+    Mute {
+        repository: String,
+        #[arg(long = "for", value_parser = duration::parse_duration, help = "how long to mute for, e.g. 30m, 2h, 7d")]
+        duration: chrono::Duration,
+    },
+    #[clap(alias = "um", about = "unmute a repository; aliased to 'um'")]
+    Unmute { repository: String },
+    #[clap(alias = "h", about = "lists past acks and unacks; aliased to 'h'")]
+    History {
+        #[arg(long)]
+        json: bool,
+    },
+    #[clap(
+        alias = "st",
+        about = "shows review and acknowledgement statistics; aliased to 'st'"
+    )]
+    Stats {
+        #[arg(long)]
+        json: bool,
+    },
+    #[clap(
+        alias = "sum",
+        about = "shows unacked and acked counts and the oldest unacked review per repository; aliased to 'sum'"
+    )]
+    Summary {
+        #[arg(long)]
+        json: bool,
+    },
+    #[clap(
+        alias = "y",
+        about = "copies the url of a pr to the clipboard; aliased to 'y'"
+    )]
+    Yank {
+        #[arg(help = "index in the unacknowledged listing or pr id, prompts if omitted")]
+        pr: Option<String>,
+    },
+    #[clap(
+        alias = "t",
+        about = "lists acknowledged and unacknowledged prs together, toggling the ones selected; aliased to 't'"
+    )]
+    Triage {},
+    #[clap(alias = "u", about = "undo the last ack or unack; aliased to 'u'")]
+    Undo {},
     #[clap(alias = "cls", about = "clear all session state; aliased to 'cls'")]
-    ClearSession {},
+    ClearSession {
+        #[arg(long, help = "print what would be cleared without saving anything")]
+        dry_run: bool,
+    },
+    #[clap(about = "prints a shell completion script")]
+    Completions {
+        #[arg(value_enum)]
+        shell: completions::Shell,
+    },
+    #[clap(about = "prints a roff man page")]
+    Man {},
+    #[clap(about = "interactively creates a config file and an empty session state")]
+    Init {},
+    #[clap(about = "adds, removes or lists tracked repositories")]
+    Repo {
+        #[command(subcommand)]
+        command: config::RepoCommand,
+    },
+    #[clap(about = "checks gh, the config and the session state for problems")]
+    Doctor {},
+    #[clap(about = "reads or edits the config file, preserving comments")]
+    Config {
+        #[command(subcommand)]
+        command: config::ConfigCommand,
+    },
+    #[clap(about = "serves newline delimited json-rpc on stdin and stdout for editor plugins")]
+    ServeStdio {},
+    #[clap(about = "records the open prs of every tracked repository for use with --offline")]
+    Record {
+        #[arg(
+            long,
+            short,
+            help = "where to write the fixture, defaults to the fixture_file config key"
+        )]
+        output: Option<PathBuf>,
+    },
+}
+
+/// A one-off query of repositories that aren't configured, which ignores the config file and
+/// keeps its own session state.
+#[derive(clap::Args, Debug)]
+struct AdHocArgs {
+    #[arg(
+        long = "repo",
+        value_name = "OWNER/NAME",
+        help = "query this repository instead of the configured ones without reading the config file, may be repeated"
+    )]
+    repositories: Vec<String>,
+    #[arg(
+        long,
+        requires = "repositories",
+        help = "author to query with --repo, defaults to the gh user"
+    )]
+    author: Option<String>,
+    #[arg(
+        long,
+        requires = "repositories",
+        help = "with --repo, neither read nor save session state"
+    )]
+    no_state: bool,
+}
+
+impl AdHocArgs {
+    fn session_config(&self) -> SessionConfig {
+        SessionConfig {
+            // gh resolves @me to the logged in user
+            author: self.author.clone().unwrap_or("@me".to_string()),
+            repositories: self.repositories.iter().cloned().collect(),
+            repository_authors: HashMap::new(),
+            ack_ttl: None,
+            stale_after: None,
+            required_reviewers: None,
+            base_branch: None,
+            archive_retention: None,
+        }
+    }
+}
+
+/// The ad-hoc query of this invocation, if it was given `--repo`.
+fn ad_hoc_query(args: &Args) -> Option<&AdHocArgs> {
+    match &args.command {
+        Command::Fetch { ad_hoc, .. } if !ad_hoc.repositories.is_empty() => Some(ad_hoc),
+        _ => None,
+    }
+}
+
+#[derive(clap::Args, Debug)]
+struct FilterArgs {
+    #[arg(
+        long = "label",
+        value_name = "LABEL",
+        help = "only list prs with this label, may be repeated to allow several"
+    )]
+    labels: Vec<String>,
+    #[arg(
+        long = "exclude-label",
+        value_name = "LABEL",
+        help = "skip prs with this label, may be repeated"
+    )]
+    exclude_labels: Vec<String>,
+    #[arg(
+        long,
+        help = "only list prs targeting this branch, overrides the base_branch config key"
+    )]
+    base: Option<String>,
+    #[arg(
+        long,
+        conflicts_with = "base",
+        help = "list prs targeting any branch, ignoring the base_branch config key"
+    )]
+    any_base: bool,
+}
+
+impl FilterArgs {
+    fn filter(&self, session: &Session) -> PrFilter {
+        let filter = PrFilter {
+            labels: self.labels.clone(),
+            exclude_labels: self.exclude_labels.clone(),
+            base: self.base.clone(),
+        };
+
+        if self.any_base {
+            filter
+        } else {
+            filter.or_session_defaults(session)
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum OutputFormat {
+    Table,
+    Json,
+    Csv,
+    Tsv,
+    Markdown,
+    Porcelain,
+}
+
+impl OutputFormat {
+    fn resolve(self, json: bool, porcelain: bool) -> OutputFormat {
+        if json {
+            OutputFormat::Json
+        } else if porcelain {
+            OutputFormat::Porcelain
+        } else {
+            self
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug)]
+enum StatusFormat {
+    Plain,
+    Waybar,
+    I3blocks,
+    Tmux,
+}
+
+#[derive(ValueEnum, Deserialize, Clone, Copy, Debug, Default)]
+#[serde(rename_all = "snake_case")]
+enum SortKey {
+    /// Most recently reviewed first
+    #[default]
+    #[serde(alias = "latest_review")]
+    Time,
+    Repo,
+    Title,
+    /// Oldest pr first
+    Age,
+    /// Largest diff first, counting added and deleted lines
+    Size,
+    /// Most changed files first
+    Files,
+}
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+enum GroupBy {
+    /// One table section per repository
+    Repo,
 }
 
 #[derive(Parser, Debug)]
@@ -59,100 +430,474 @@ struct Args {
         help = "path to session state, also set by GHPRS_STATE_FILE env variable"
     )]
     session_state_path: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "named profile from the config file, also set by GHPRS_PROFILE env variable"
+    )]
+    profile: Option<String>,
 
     #[arg(long, short, default_value_t = false)]
     force: bool,
 
+    #[arg(
+        long,
+        value_enum,
+        default_value_t = ColorChoice::Auto,
+        help = "when to color output, auto respects NO_COLOR"
+    )]
+    color: ColorChoice,
+
+    #[arg(long, global = true, value_enum, default_value_t = SortKey::Time, help = "order of pr listings")]
+    sort: SortKey,
+    #[arg(long, global = true, help = "reverse the order of pr listings")]
+    reverse: bool,
+    #[arg(
+        long,
+        global = true,
+        value_enum,
+        help = "split pr tables into sections with subtotals"
+    )]
+    group_by: Option<GroupBy>,
+
+    #[arg(
+        long,
+        global = true,
+        help = "show additions, deletions and changed files in pr tables"
+    )]
+    size: bool,
+
+    #[arg(long, global = true, help = "never pipe listings through $PAGER")]
+    no_pager: bool,
+
+    #[arg(
+        long,
+        global = true,
+        help = "serve prs from the fixture written by the record command instead of calling gh"
+    )]
+    offline: bool,
+
+    #[arg(
+        short,
+        long,
+        global = true,
+        action = clap::ArgAction::Count,
+        help = "print more diagnostics, -v for info and -vv for debug, overrides GHPRS_LOG"
+    )]
+    verbose: u8,
+    #[arg(
+        short,
+        long,
+        global = true,
+        conflicts_with = "verbose",
+        help = "only print errors, overrides GHPRS_LOG"
+    )]
+    quiet: bool,
+
     #[command(subcommand)]
     command: Command,
 }
 
+/// A tracked repository, either `"owner/name"` or `{ name = "owner/name", author = "login" }` to
+/// track prs by a different author in that repository.
+#[derive(Clone, Deserialize)]
+#[serde(untagged)]
+enum RepositoryEntry {
+    Name(String),
+    WithAuthor {
+        name: String,
+        author: Option<String>,
+    },
+}
+
+impl RepositoryEntry {
+    fn name(&self) -> &str {
+        match self {
+            RepositoryEntry::Name(name) | RepositoryEntry::WithAuthor { name, .. } => name,
+        }
+    }
+}
+
 #[derive(Clone, Deserialize)]
 struct Config {
     pub author: String,
-    pub repositories: HashSet<String>,
+    pub repositories: Vec<RepositoryEntry>,
+    pub session_state_file: Option<PathBuf>,
+    pub fixture_file: Option<PathBuf>,
+    pub ack_ttl: Option<u32>,
+    pub stale_after: Option<u32>,
+    pub required_reviewers: Option<HashSet<String>>,
+    pub base_branch: Option<String>,
+    pub archive_retention: Option<u32>,
+    #[serde(default)]
+    pub profiles: HashMap<String, Profile>,
+}
+
+/// A named set of overrides for the top level config, unset keys are inherited.
+#[derive(Clone, Deserialize)]
+struct Profile {
+    pub author: Option<String>,
+    pub repositories: Option<Vec<RepositoryEntry>>,
     pub session_state_file: Option<PathBuf>,
+    pub fixture_file: Option<PathBuf>,
+    pub ack_ttl: Option<u32>,
+    pub stale_after: Option<u32>,
+    pub required_reviewers: Option<HashSet<String>>,
+    pub base_branch: Option<String>,
+    pub archive_retention: Option<u32>,
+}
+
+impl Config {
+    fn repository_names(&self) -> HashSet<String> {
+        self.repositories
+            .iter()
+            .map(|repository| repository.name().to_string())
+            .collect()
+    }
+
+    /// Authors of repositories that override the top level author.
+    fn repository_authors(&self) -> HashMap<String, String> {
+        self.repositories
+            .iter()
+            .filter_map(|repository| match repository {
+                RepositoryEntry::WithAuthor {
+                    name,
+                    author: Some(author),
+                } => Some((name.clone(), author.clone())),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Applies the overrides of profile `name`, giving it its own state file unless one is set.
+    fn with_profile(self, name: Option<&str>) -> anyhow::Result<Config> {
+        let Some(name) = name else {
+            return Ok(self);
+        };
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            let mut known_profiles: Vec<&str> = self.profiles.keys().map(String::as_str).collect();
+            known_profiles.sort();
+            if known_profiles.is_empty() {
+                bail!("Unknown profile '{name}', config defines no [profiles.<name>] tables")
+            }
+            bail!(
+                "Unknown profile '{name}', config defines: {}",
+                known_profiles.join(", ")
+            )
+        };
+
+        Ok(Config {
+            author: profile.author.unwrap_or(self.author),
+            repositories: profile.repositories.unwrap_or(self.repositories),
+            session_state_file: Some(
+                profile
+                    .session_state_file
+                    .unwrap_or_else(|| state_directory().join(format!("ghprs-state-{name}.json"))),
+            ),
+            fixture_file: profile.fixture_file.or(self.fixture_file),
+            ack_ttl: profile.ack_ttl.or(self.ack_ttl),
+            stale_after: profile.stale_after.or(self.stale_after),
+            required_reviewers: profile.required_reviewers.or(self.required_reviewers),
+            base_branch: profile.base_branch.or(self.base_branch),
+            archive_retention: profile.archive_retention.or(self.archive_retention),
+            profiles: HashMap::new(),
+        })
+    }
 }
 
 impl From<Config> for SessionConfig {
     fn from(value: Config) -> Self {
+        let repositories = value.repository_names();
+        let repository_authors = value.repository_authors();
         let Config {
             author,
-            repositories,
+            repositories: _,
             session_state_file: _,
+            fixture_file: _,
+            ack_ttl,
+            stale_after,
+            required_reviewers,
+            base_branch,
+            archive_retention,
+            profiles: _,
         } = value;
 
         SessionConfig {
             author,
             repositories,
+            repository_authors,
+            ack_ttl,
+            stale_after,
+            required_reviewers,
+            base_branch,
+            archive_retention,
         }
     }
 }
 
-fn save_session_config<P: AsRef<Path>>(
-    session_config: &SessionConfig,
-    session_config_path: P,
-) -> anyhow::Result<()> {
-    let mut file = std::fs::File::create(session_config_path)?;
-    let config_str = toml::to_string(session_config)?;
-    file.write_all(config_str.as_bytes())?;
+const STATE_LOCK_TIMEOUT: Duration = Duration::from_secs(5);
 
-    Ok(())
+/// Takes an advisory lock on `<state>.lock`, held until the returned file is dropped, so
+/// concurrent invocations don't clobber each other's writes to the state file.
+fn lock_session_state(session_state_path: &Path) -> anyhow::Result<std::fs::File> {
+    let mut lock_path = session_state_path.as_os_str().to_owned();
+    lock_path.push(".lock");
+    let lock_path = PathBuf::from(lock_path);
+
+    if let Some(parent) = lock_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let lock_file = std::fs::OpenOptions::new()
+        .create(true)
+        .truncate(false)
+        .write(true)
+        .open(&lock_path)?;
+
+    let started = Instant::now();
+    loop {
+        match lock_file.try_lock() {
+            Ok(()) => return Ok(lock_file),
+            Err(TryLockError::WouldBlock) if started.elapsed() < STATE_LOCK_TIMEOUT => {
+                std::thread::sleep(Duration::from_millis(50));
+            }
+            Err(TryLockError::WouldBlock) => bail!(
+                "Session state {} is locked by another ghp invocation, gave up after {}s",
+                session_state_path.display(),
+                STATE_LOCK_TIMEOUT.as_secs()
+            ),
+            Err(TryLockError::Error(e)) => {
+                bail!("Failed to lock {}: {e}", lock_path.display())
+            }
+        }
+    }
+}
+
+/// A session loaded under the state lock. `save` writes it back and releases the lock, dropping
+/// it instead releases the lock and discards any changes.
+///
+/// Other invocations wait on the lock for as long as this lives, so keep prompts and the pager
+/// until after `save`.
+struct LockedSession {
+    session: Session,
+    backup: Option<StateBackup>,
+    _lock: std::fs::File,
+}
+
+impl LockedSession {
+    /// Saves the session and releases the lock, returning the session to render from.
+    fn save(self, args: &Args) -> anyhow::Result<Session> {
+        if let Some(backup) = &self.backup {
+            backup.write(&session_state_path(args))?;
+        }
+        save_session(&self.session, args)?;
+        Ok(self.session)
+    }
+
+    /// Releases the lock without saving, for dry runs that must leave the state file untouched.
+    fn release(self) -> Session {
+        self.session
+    }
+}
+
+impl Deref for LockedSession {
+    type Target = Session;
+
+    fn deref(&self) -> &Session {
+        &self.session
+    }
+}
+
+impl DerefMut for LockedSession {
+    fn deref_mut(&mut self) -> &mut Session {
+        &mut self.session
+    }
+}
+
+/// Locks and loads the session, refreshing it from the fixture when offline. `force` makes the
+/// next listing refresh from github, only the first load of an invocation should pass it.
+async fn lock_session(args: &Args, force: bool) -> anyhow::Result<LockedSession> {
+    let session_state_path = session_state_path(args);
+    let lock = lock_session_state(&session_state_path)?;
+    if let Err(e) = migrate_legacy_state(&session_state_path) {
+        logging::error!(
+            "failed to move session state out of the config directory",
+            error = e
+        );
+    }
+    let (mut session, backup) = read_session(args)?;
+    if force {
+        session.force_update_session_prs();
+    }
+    update_offline_session_prs(&mut session, args).await?;
+
+    Ok(LockedSession {
+        session,
+        backup,
+        _lock: lock,
+    })
 }
 
+/// Writes the state to a temporary file renamed over `session_state_path`, so invocations reading
+/// it without the lock never see it half written.
 fn save_session_state<P: AsRef<Path>>(
     session_state: &SessionState,
     session_state_path: P,
 ) -> anyhow::Result<()> {
-    let file = std::fs::File::create(session_state_path)?;
+    let session_state_path = session_state_path.as_ref();
+    if let Some(parent) = session_state_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut temporary_path = session_state_path.as_os_str().to_owned();
+    temporary_path.push(".tmp");
+    let file = std::fs::File::create(&temporary_path)?;
     serde_json::to_writer(file, session_state)?;
+    std::fs::rename(&temporary_path, session_state_path)?;
 
     Ok(())
 }
 
-fn config_directory() -> PathBuf {
-    env::var("XDG_CONFIG_HOME")
-        .map(PathBuf::from)
-        .unwrap_or(PathBuf::from(env::var("HOME").ok().unwrap()).join(".config"))
+/// A default state file left in the config directory by older versions that hasn't been moved
+/// to `session_state_path` yet.
+fn legacy_state_path(session_state_path: &Path) -> Option<PathBuf> {
+    let parent = session_state_path.parent()?;
+    let legacy_path = legacy_state_directory().join(session_state_path.file_name()?);
+    (parent == state_directory() && !session_state_path.exists() && legacy_path.exists())
+        .then_some(legacy_path)
+}
+
+/// Moves a default state file left in the config directory by older versions to the state directory.
+fn migrate_legacy_state(session_state_path: &Path) -> anyhow::Result<()> {
+    let Some(legacy_path) = legacy_state_path(session_state_path) else {
+        return Ok(());
+    };
+
+    if let Some(parent) = session_state_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    if std::fs::rename(&legacy_path, session_state_path).is_err() {
+        // Renaming fails across filesystems, fall back to copying
+        std::fs::copy(&legacy_path, session_state_path)?;
+        std::fs::remove_file(&legacy_path)?;
+    }
+    logging::warn!(
+        "moved session state out of the config directory",
+        from = legacy_path.display(),
+        to = session_state_path.display()
+    );
+
+    Ok(())
 }
 
 const SESSION_CONFIG_FILENAME: &str = "ghprs.toml";
 const SESSION_STATE_FILENAME: &str = "ghprs-state.json";
+const AD_HOC_SESSION_STATE_FILENAME: &str = "ghprs-state-adhoc.json";
+const FIXTURE_FILENAME: &str = "ghprs-fixture.json";
 
-fn save_session(session: &Session, args: &Args) -> anyhow::Result<()> {
-    let session_config_path = args
-        .session_config_path
+fn session_config_path(args: &Args) -> PathBuf {
+    args.session_config_path
         .clone()
         .or(env::var("GHPRS_CONFIG_FILE").ok().map(|s| s.into()))
-        .unwrap_or(config_directory().join(SESSION_CONFIG_FILENAME));
+        .unwrap_or(config_directory().join(SESSION_CONFIG_FILENAME))
+}
+
+fn profile(args: &Args) -> Option<String> {
+    args.profile
+        .clone()
+        .or(env::var("GHPRS_PROFILE").ok())
+        .filter(|profile| !profile.is_empty())
+}
 
-    let session_state_path = args
-        .session_state_path
+fn session_state_path(args: &Args) -> PathBuf {
+    // Ad-hoc queries would drop every tracked pr of other repositories from the usual state
+    if ad_hoc_query(args).is_some() {
+        return args
+            .session_state_path
+            .clone()
+            .unwrap_or(state_directory().join(AD_HOC_SESSION_STATE_FILENAME));
+    }
+
+    args.session_state_path
         .clone()
         .or(env::var("GHPRS_STATE_FILE").ok().map(|s| s.into()))
-        .unwrap_or(config_directory().join(SESSION_STATE_FILENAME));
+        .or_else(|| {
+            load_config(args)
+                .ok()
+                .and_then(|config| config.session_state_file)
+        })
+        .unwrap_or(state_directory().join(SESSION_STATE_FILENAME))
+}
+
+fn fixture_path(args: &Args) -> PathBuf {
+    load_config(args)
+        .ok()
+        .and_then(|config| config.fixture_file)
+        .unwrap_or(state_directory().join(FIXTURE_FILENAME))
+}
+
+fn save_fixture(fixture: &Fixture, fixture_path: &Path) -> anyhow::Result<()> {
+    if let Some(parent) = fixture_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let file = std::fs::File::create(fixture_path)?;
+    serde_json::to_writer_pretty(file, fixture)?;
+
+    Ok(())
+}
 
-    let (session_config, session_state): (SessionConfig, SessionState) = session.clone().into();
-    if let Err(e) = save_session_config(&session_config, session_config_path) {
-        eprintln!("Failed to save session config: {e}");
+fn load_fixture(fixture_path: &Path) -> anyhow::Result<Fixture> {
+    let contents = match std::fs::read_to_string(fixture_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => bail!(
+            "No fixture at {}, run the record command while online first",
+            fixture_path.display()
+        ),
+        Err(e) => bail!("Failed to read fixture {}: {e}", fixture_path.display()),
     };
 
+    serde_json::from_str(&contents)
+        .map_err(|e| anyhow!("Could not parse fixture {}: {e}", fixture_path.display()))
+}
+
+/// With `--offline`, refreshes the session from the recorded fixture. The session is fresh
+/// afterwards, so nothing later in the run reaches for gh.
+async fn update_offline_session_prs(session: &mut Session, args: &Args) -> anyhow::Result<()> {
+    if !args.offline {
+        return Ok(());
+    }
+
+    let fixture = load_fixture(&fixture_path(args))?;
+    if session.update_session_prs_with(&fixture).await {
+        // The recorded prs may be older than the watermarks this set, make the next online
+        // refresh a full one
+        session.last_full_fetch_time = None;
+    }
+
+    Ok(())
+}
+
+/// Saves session state, the config file is only ever written by the `config` subcommand.
+fn save_session(session: &Session, args: &Args) -> anyhow::Result<()> {
+    if ad_hoc_query(args).is_some_and(|ad_hoc| ad_hoc.no_state) {
+        return Ok(());
+    }
+    let session_state_path = session_state_path(args);
+
+    let (_, session_state): (SessionConfig, SessionState) = session.clone().into();
     if let Err(e) = save_session_state(&session_state, session_state_path) {
-        eprintln!("Failed to save session state: {e}");
+        logging::error!("failed to save session state", error = e);
     };
 
     Ok(())
 }
 
-fn load_session(args: &Args) -> anyhow::Result<Session> {
-    let session_config_file_path = args
-        .session_config_path
-        .clone()
-        .or(env::var("GHPRS_CONFIG_FILE").ok().map(|s| s.into()))
-        .unwrap_or(config_directory().join(SESSION_CONFIG_FILENAME));
+fn load_config(args: &Args) -> anyhow::Result<Config> {
+    let session_config_file_path = session_config_path(args);
 
     let Ok(mut config_file) = std::fs::File::open(session_config_file_path) else {
-        bail!("Need to provide config file, path is specified in args, as GHPRS_CONFIG_FILE env var or at XDG_CONFIG_HOME/ghprs.toml")
+        bail!(
+            "Need to provide config file, path is specified in args, as GHPRS_CONFIG_FILE env var or at {}",
+            config_directory().join(SESSION_CONFIG_FILENAME).display()
+        )
     };
     let mut session_file_contents = String::new();
     if let Err(e) = config_file.read_to_string(&mut session_file_contents) {
@@ -163,20 +908,73 @@ fn load_session(args: &Args) -> anyhow::Result<Session> {
         Ok(config) => config,
         Err(e) => bail!("Could not parse config: {e}"),
     };
+    config.with_profile(profile(args).as_deref())
+}
 
-    let session_state_file_path = args
-        .session_config_path
-        .clone()
-        .or(env::var("GHPRS_CONFIG_FILE").ok().map(|s| s.into()))
-        .or(config.session_state_file.clone())
-        .unwrap_or(config_directory().join(SESSION_STATE_FILENAME));
+/// A state file as written by an older version, kept next to it once the upgraded state is saved
+/// in case a migration loses something.
+struct StateBackup {
+    version: u32,
+    contents: String,
+}
 
-    let state: SessionState = std::fs::File::open(session_state_file_path)
-        .ok()
-        .and_then(|file| serde_json::from_reader(file).ok())
-        .unwrap_or_default();
+impl StateBackup {
+    fn write(&self, session_state_path: &Path) -> anyhow::Result<()> {
+        let mut backup_path = session_state_path.as_os_str().to_owned();
+        backup_path.push(format!(".v{}.bak", self.version));
+        std::fs::write(&backup_path, &self.contents)?;
+
+        Ok(())
+    }
+}
+
+/// Loads the session for reading, nothing is written back.
+fn load_session(args: &Args) -> anyhow::Result<Session> {
+    read_session(args).map(|(session, _)| session)
+}
+
+/// Loads the session, along with the state file as it was when it had to be upgraded.
+fn read_session(args: &Args) -> anyhow::Result<(Session, Option<StateBackup>)> {
+    if let Some(ad_hoc) = ad_hoc_query(args) {
+        if ad_hoc.no_state {
+            let session = Session::new(ad_hoc.session_config(), SessionState::default());
+            return Ok((session, None));
+        }
+    }
+
+    let config: SessionConfig = match ad_hoc_query(args) {
+        Some(ad_hoc) => ad_hoc.session_config(),
+        None => load_config(args)?.into(),
+    };
+    // Only invocations holding the lock move a legacy state file, until then it is read in place
+    let session_state_file_path = session_state_path(args);
+    let session_state_file_path =
+        legacy_state_path(&session_state_file_path).unwrap_or(session_state_file_path);
+
+    let contents = match std::fs::read_to_string(&session_state_file_path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+        Err(e) => bail!(
+            "Failed to read session state {}: {e}",
+            session_state_file_path.display()
+        ),
+    };
+    let (state, backup) = if contents.is_empty() {
+        (SessionState::default(), None)
+    } else {
+        let (state, version) = migrations::parse_state(&contents).map_err(|e| {
+            anyhow!(
+                "Could not load session state {}: {e}, move it aside to start a fresh session",
+                session_state_file_path.display()
+            )
+        })?;
+
+        let backup =
+            (version < migrations::STATE_VERSION).then_some(StateBackup { version, contents });
+        (state, backup)
+    };
 
-    Ok(Session::new(config.into(), state))
+    Ok((Session::new(config, state), backup))
 }
 
 #[derive(Serialize, Clone, Debug, Tabled)]
@@ -184,48 +982,491 @@ struct PrettyGithubPRStatus {
     pub num: usize,
     pub title: String,
     pub repository: String,
+    #[tabled(skip)]
+    pub created_at: Option<DateTime<Local>>,
+    /// Time since the pr was opened, only shown in tables since JSON has `created_at`.
+    #[serde(skip)]
+    pub age: String,
     pub latest_review_time: DateTime<Local>,
+    #[tabled(display_with = "display_reviewers")]
+    pub reviewers: Vec<String>,
+    #[tabled(rename = "reviews")]
+    pub review_counts: ReviewCounts,
+    #[tabled(rename = "decision")]
+    pub review_decision: GithubReviewDecision,
+    #[tabled(display_with = "display_conflicts")]
+    pub conflicts: bool,
+    pub additions: u64,
+    pub deletions: u64,
+    #[tabled(rename = "files")]
+    pub changed_files: u64,
+    #[tabled(skip)]
+    pub labels: Vec<String>,
+    #[tabled(skip)]
+    pub base_branch: Option<String>,
 }
 
-fn prettyify_prs(prs: &[GithubPRStatus]) -> Vec<PrettyGithubPRStatus> {
-    prs.iter()
-        .enumerate()
-        .filter_map(|(num, pr)| -> Option<PrettyGithubPRStatus> {
-            Some(PrettyGithubPRStatus {
-                num,
-                title: pr.title.clone(),
-                repository: pr.repository.clone(),
-                latest_review_time: pr.latest_review_time()?.into(),
-            })
-        })
-        .collect()
+fn display_reviewers(reviewers: &[String]) -> String {
+    reviewers.join(", ")
 }
 
-fn select_pr(prs: &[GithubPRStatus]) -> Option<String> {
-    if prs.is_empty() {
-        println!("{}", Table::new(prettyify_prs(prs)));
-        return None;
-    }
+fn display_conflicts(conflicts: &bool) -> String {
+    if *conflicts { "⚠" } else { "" }.to_string()
+}
+
+fn prettyify_pr(num: usize, pr: &GithubPRStatus) -> Option<PrettyGithubPRStatus> {
+    Some(PrettyGithubPRStatus {
+        num,
+        title: pr.title.clone(),
+        repository: pr.repository.clone(),
+        created_at: pr.created_at.map(Into::into),
+        age: pr
+            .created_at
+            .map(|created_at| duration::format_duration(Utc::now() - created_at))
+            .unwrap_or_default(),
+        latest_review_time: pr.latest_review_time()?.into(),
+        reviewers: pr.reviewers().into_iter().map(str::to_string).collect(),
+        review_counts: pr.review_counts(),
+        review_decision: pr.review_decision,
+        conflicts: pr.has_conflicts(),
+        additions: pr.additions,
+        deletions: pr.deletions,
+        changed_files: pr.changed_files,
+        labels: pr.labels.clone(),
+        base_branch: pr.base_branch.clone(),
+    })
+}
+
+/// Whether the newest actionable review of `pr` falls within the window starting at `since`,
+/// prs without actionable reviews never do.
+fn reviewed_since(session: &Session, pr: &GithubPRStatus, since: Option<DateTime<Utc>>) -> bool {
+    since.is_none_or(|since| {
+        session
+            .latest_actionable_review_time(pr)
+            .is_some_and(|reviewed| reviewed >= since)
+    })
+}
+
+/// Orders prs before they are numbered so interactive indices match what was displayed.
+/// Grouped listings keep each repository's prs together so indices still count up down the page.
+fn sort_prs(prs: Vec<GithubPRStatus>, args: &Args) -> Vec<GithubPRStatus> {
+    let mut prs = sort_prs_by(prs, args.sort, args.reverse);
+    if args.group_by == Some(GroupBy::Repo) {
+        prs.sort_by(|a, b| a.repository.cmp(&b.repository));
+    }
+    prs
+}
+
+fn sort_prs_by(mut prs: Vec<GithubPRStatus>, sort: SortKey, reverse: bool) -> Vec<GithubPRStatus> {
+    prs.sort_by(|a, b| {
+        let ordering = match sort {
+            SortKey::Time => b.latest_review_time().cmp(&a.latest_review_time()),
+            SortKey::Repo => a.repository.cmp(&b.repository),
+            SortKey::Title => a.title.cmp(&b.title),
+            SortKey::Age => a.created_at.cmp(&b.created_at),
+            SortKey::Size => b.diff_size().cmp(&a.diff_size()),
+            SortKey::Files => b.changed_files.cmp(&a.changed_files),
+        };
+
+        ordering.then_with(|| a.id.cmp(&b.id))
+    });
+
+    if reverse {
+        prs.reverse();
+    }
+
+    prs
+}
+
+/// Colours a row by the state of its newest actionable review, dimmed once that review is stale.
+fn pr_style(pr: &GithubPRStatus, options: TableOptions) -> Vec<Style> {
+    let Some(latest_review) = options.session.latest_actionable_review(pr) else {
+        return Vec::new();
+    };
+
+    let mut styles = Vec::new();
+    match latest_review.state {
+        GithubReviewState::ChangesRequested => styles.push(Style::Red),
+        GithubReviewState::Approved => styles.push(Style::Green),
+        _ => {}
+    }
+    if Utc::now().signed_duration_since(latest_review.submitted_at) > options.stale_after {
+        styles.push(Style::Dim);
+    }
+    styles
+}
+
+/// How pr tables are rendered.
+#[derive(Clone, Copy, Debug)]
+struct TableOptions<'a> {
+    /// Decides which reviews are actionable when styling rows.
+    session: &'a Session,
+    /// Rows whose newest actionable review is older than this are dimmed.
+    stale_after: chrono::Duration,
+    /// Whether to show the diff size columns.
+    size: bool,
+    /// Splits the table into sections, `None` renders a single table.
+    group_by: Option<GroupBy>,
+}
+
+fn table_options<'a>(session: &'a Session, args: &Args) -> TableOptions<'a> {
+    TableOptions {
+        session,
+        stale_after: session.stale_after(),
+        size: args.size,
+        group_by: args.group_by,
+    }
+}
+
+/// Columns that are only shown when asked for, to keep the default table narrow.
+fn disable_optional_columns(table: &mut Table, options: TableOptions) {
+    if !options.size {
+        for column in ["additions", "deletions", "files"] {
+            table.with(Disable::column(ByColumnName::new(column)));
+        }
+    }
+}
+
+fn pr_table(prs: &[GithubPRStatus], options: TableOptions) -> String {
+    render_pr_rows(prs, prettyify_prs(prs), |pretty_pr| pretty_pr.num, options)
+}
+
+/// Renders rows showing `prs`, `num` giving the index of the pr a row shows, as a single table or
+/// as one section per group headed by its subtotal.
+fn render_pr_rows<T: Tabled>(
+    prs: &[GithubPRStatus],
+    rows: Vec<T>,
+    num: impl Fn(&T) -> usize,
+    options: TableOptions,
+) -> String {
+    let Some(GroupBy::Repo) = options.group_by.filter(|_| !rows.is_empty()) else {
+        return pr_rows_table(prs, rows, &num, options);
+    };
+
+    let mut sections: BTreeMap<&str, Vec<T>> = BTreeMap::new();
+    for row in rows {
+        sections
+            .entry(&prs[num(&row)].repository)
+            .or_default()
+            .push(row);
+    }
+
+    let total: usize = sections.values().map(Vec::len).sum();
+    let mut sections: Vec<String> = sections
+        .into_iter()
+        .map(|(repository, rows)| {
+            let heading = format!("{repository} ({})", count_prs(rows.len()));
+            format!(
+                "{}\n{}",
+                Style::Bold.paint(&heading),
+                pr_rows_table(prs, rows, &num, options)
+            )
+        })
+        .collect();
+    let repositories = if sections.len() == 1 {
+        "repository"
+    } else {
+        "repositories"
+    };
+    sections.push(format!(
+        "{} across {} {repositories}",
+        count_prs(total),
+        sections.len()
+    ));
+    sections.join("\n\n")
+}
+
+fn pr_rows_table<T: Tabled>(
+    prs: &[GithubPRStatus],
+    rows: Vec<T>,
+    num: &impl Fn(&T) -> usize,
+    options: TableOptions,
+) -> String {
+    let styles: Vec<Vec<Style>> = rows
+        .iter()
+        .map(|row| pr_style(&prs[num(row)], options))
+        .collect();
+
+    let mut table = Table::new(rows);
+    disable_optional_columns(&mut table, options);
+    paint_table_rows(&table.to_string(), &styles)
+}
+
+fn count_prs(count: usize) -> String {
+    format!("{count} {}", if count == 1 { "PR" } else { "PRs" })
+}
+
+fn pr_records(prs: &[GithubPRStatus], delimiter: Delimiter) -> String {
+    let records: Vec<Vec<String>> = prs.iter().map(pr_record).collect();
+    delimited(&PR_RECORD_HEADER, &records, delimiter)
+}
+
+fn prettyify_prs(prs: &[GithubPRStatus]) -> Vec<PrettyGithubPRStatus> {
+    prs.iter()
+        .enumerate()
+        .filter_map(|(num, pr)| prettyify_pr(num, pr))
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+struct PrettyAcknowledgedPRStatus {
+    #[serde(flatten)]
+    #[tabled(inline)]
+    pub status: PrettyGithubPRStatus,
+    #[tabled(display_with = "display_note")]
+    pub note: Option<String>,
+}
+
+fn display_note(note: &Option<String>) -> String {
+    note.clone().unwrap_or_default()
+}
+
+fn prettyify_acknowledged_prs(
+    session: &Session,
+    prs: &[GithubPRStatus],
+) -> Vec<PrettyAcknowledgedPRStatus> {
+    prs.iter()
+        .enumerate()
+        .filter_map(|(num, pr)| {
+            Some(PrettyAcknowledgedPRStatus {
+                status: prettyify_pr(num, pr)?,
+                note: session.acknowledgement_note(&pr.id).map(str::to_string),
+            })
+        })
+        .collect()
+}
+
+fn acknowledged_pr_records(
+    session: &Session,
+    prs: &[GithubPRStatus],
+    delimiter: Delimiter,
+) -> String {
+    let header: Vec<&str> = PR_RECORD_HEADER.into_iter().chain(["note"]).collect();
+    let records: Vec<Vec<String>> = prs
+        .iter()
+        .map(|pr| {
+            let mut record = pr_record(pr);
+            let note = session.acknowledgement_note(&pr.id).unwrap_or_default();
+            record.push(note.to_string());
+            record
+        })
+        .collect();
+
+    delimited(&header, &records, delimiter)
+}
+
+#[derive(Clone, Debug, Tabled)]
+struct PrettyTriagePr {
+    /// First so the state is visible however wide the table gets.
+    #[tabled(rename = "acked", display_with = "display_acknowledged")]
+    pub acknowledged: bool,
+    #[tabled(inline)]
+    pub status: PrettyGithubPRStatus,
+}
+
+fn display_acknowledged(acknowledged: &bool) -> String {
+    if *acknowledged { "✓" } else { "" }.to_string()
+}
+
+fn triage_table(session: &Session, prs: &[GithubPRStatus], options: TableOptions) -> String {
+    let pretty_prs: Vec<PrettyTriagePr> = prs
+        .iter()
+        .enumerate()
+        .filter_map(|(num, pr)| {
+            Some(PrettyTriagePr {
+                status: prettyify_pr(num, pr)?,
+                acknowledged: session
+                    .prs
+                    .get(&pr.id)
+                    .is_some_and(|session_pr| session_pr.is_acknowledged()),
+            })
+        })
+        .collect();
+    render_pr_rows(prs, pretty_prs, |pretty_pr| pretty_pr.status.num, options)
+}
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+struct PrettyArchivedPr {
+    pub title: String,
+    pub repository: String,
+    pub state: GithubPRState,
+    pub archived_at: DateTime<Local>,
+    pub acknowledged: bool,
+    #[tabled(display_with = "display_note")]
+    pub note: Option<String>,
+}
+
+fn prettyify_archived_prs(prs: &[ArchivedPr]) -> Vec<PrettyArchivedPr> {
+    prs.iter()
+        .map(|archived| PrettyArchivedPr {
+            title: archived.pr.title.clone(),
+            repository: archived.pr.repository.clone(),
+            state: archived.state,
+            archived_at: archived.archived_at.into(),
+            acknowledged: archived.acknowledged,
+            note: archived.note.clone(),
+        })
+        .collect()
+}
+
+/// A line like "since last check: 2 PRs merged, 1 closed", or nothing if no pr was archived.
+fn outcome_summary(archived: &[ArchivedPr]) -> Option<String> {
+    if archived.is_empty() {
+        return None;
+    }
+
+    let merged = archived
+        .iter()
+        .filter(|archived| archived.state == GithubPRState::Merged)
+        .count();
+    let closed = archived.len() - merged;
+    let prs = if merged == 1 { "PR" } else { "PRs" };
+
+    Some(format!(
+        "since last check: {merged} {prs} merged, {closed} closed"
+    ))
+}
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+struct PrettyHistoryEntry {
+    pub time: DateTime<Local>,
+    pub operation: String,
+    pub title: String,
+    pub repository: String,
+}
+
+fn prettyify_history(history: &[HistoryEntry]) -> Vec<PrettyHistoryEntry> {
+    history
+        .iter()
+        .rev()
+        .map(|entry| PrettyHistoryEntry {
+            time: entry.time.into(),
+            operation: entry.operation.to_string(),
+            title: entry.pr.title.clone(),
+            repository: entry.pr.repository.clone(),
+        })
+        .collect()
+}
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+struct RepositoryCount {
+    pub repository: String,
+    pub count: usize,
+}
+
+fn count_by_repository(session: &Session, prs: &[GithubPRStatus]) -> Vec<RepositoryCount> {
+    let mut counts: BTreeMap<&str, usize> = session
+        .repositories
+        .iter()
+        .map(|repository| (repository.as_str(), 0))
+        .collect();
+
+    for pr in prs {
+        *counts.entry(&pr.repository).or_default() += 1;
+    }
+
+    counts
+        .into_iter()
+        .map(|(repository, count)| RepositoryCount {
+            repository: repository.to_string(),
+            count,
+        })
+        .collect()
+}
+
+fn print_status(prs: &[GithubPRStatus], format: StatusFormat) {
+    let count = prs.len();
+    let tooltip = prs
+        .iter()
+        .map(|pr| format!("{}: {}", pr.repository, pr.title))
+        .collect::<Vec<String>>()
+        .join("\n");
+
+    match format {
+        StatusFormat::Plain => println!("{count}"),
+        StatusFormat::Waybar => println!(
+            "{}",
+            json!({
+                "text": count.to_string(),
+                "tooltip": tooltip,
+                "class": if count > 0 { "unacknowledged" } else { "none" },
+            })
+        ),
+        StatusFormat::I3blocks => {
+            // i3blocks reads full text, short text and colour from consecutive lines.
+            println!("{count}\n{count}");
+            if count > 0 {
+                println!("#FF5555");
+            }
+        }
+        StatusFormat::Tmux => {
+            if count > 0 {
+                println!("#[fg=red,bold]PR {count}#[default]");
+            } else {
+                println!("#[fg=colour244]PR 0#[default]");
+            }
+        }
+    }
+}
+
+/// Prompts for a pr by index, or by a fuzzy search over "repo: title" which narrows the table
+/// until a single pr matches.
+fn select_pr(prs: &[GithubPRStatus], options: TableOptions) -> Option<String> {
+    if prs.is_empty() {
+        println!("{}", pr_table(prs, options));
+        return None;
+    }
 
+    let mut candidates = prs.to_vec();
     let mut buffer = String::new();
 
     let pr = loop {
-        print!("{}\n>> Enter index: ", Table::new(prettyify_prs(prs)));
+        print!(
+            "{}\n{}",
+            pr_table(&candidates, options),
+            Style::Bold.paint(">> Enter index or search: ")
+        );
         std::io::stdout().flush().unwrap();
-        io::stdin().read_line(&mut buffer).unwrap();
+        buffer.clear();
+        if io::stdin().read_line(&mut buffer).unwrap() == 0 {
+            eprintln!();
+            std::process::exit(1);
+        }
 
+        let prs = &candidates;
         match str::parse::<usize>(buffer.trim()) {
             Ok(index) => {
                 break match prs.get(index) {
                     Some(pr_id) => pr_id,
                     None => {
-                        eprintln!(">> ERROR: Invalid index {index}");
+                        eprintln!(
+                            "{}",
+                            Style::Red.paint(&format!(">> ERROR: Invalid index {index}"))
+                        );
                         continue;
                     }
                 }
             }
-            Err(e) => {
-                eprintln!(">> ERROR: Invalid index: {e}");
+            Err(_) => {
+                let query = buffer.trim();
+                let labels: Vec<String> = prs
+                    .iter()
+                    .map(|pr| format!("{}: {}", pr.repository, pr.title))
+                    .collect();
+                let matches = fuzzy::fuzzy_filter(query, &labels);
+
+                match matches.as_slice() {
+                    [] => {
+                        eprintln!(
+                            "{}",
+                            Style::Red.paint(&format!(">> ERROR: No pr matching '{query}'"))
+                        );
+                    }
+                    [index] => break &prs[*index],
+                    _ => {
+                        candidates = matches.iter().map(|index| prs[*index].clone()).collect();
+                    }
+                }
                 continue;
             }
         };
@@ -236,23 +1477,336 @@ fn select_pr(prs: &[GithubPRStatus]) -> Option<String> {
     Some(pr.id.clone())
 }
 
-fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// Lists acknowledged and unacknowledged prs in one table, toggling the acknowledgement of each pr
+/// selected by index or search until an empty line or end of input. The session is saved and
+/// unlocked while waiting for input, each toggle applies to a fresh load.
+async fn triage(session: LockedSession, args: &Args) -> anyhow::Result<()> {
+    // Toggles stay locked until the next listing saves them
+    let mut toggled = Some(session);
+    let mut buffer = String::new();
+    loop {
+        let mut locked = match toggled.take() {
+            Some(session) => session,
+            None => lock_session(args, false).await?,
+        };
+        let mut prs = unacknowledged_prs(&mut locked).await?;
+        prs.extend(acknowledged_prs(&mut locked).await?);
+        let session = locked.save(args)?;
+        let prs = sort_prs(prs, args);
+        if prs.is_empty() {
+            eprintln!("> No prs <");
+            return Ok(());
+        }
+
+        print!(
+            "{}\n{}",
+            triage_table(&session, &prs, table_options(&session, args)),
+            Style::Bold.paint(">> Enter index or search to toggle, nothing to finish: ")
+        );
+        std::io::stdout().flush()?;
+        buffer.clear();
+        if io::stdin().read_line(&mut buffer)? == 0 || buffer.trim().is_empty() {
+            return Ok(());
+        }
+
+        let query = buffer.trim();
+        let pr = match str::parse::<usize>(query) {
+            Ok(index) => prs.get(index),
+            Err(_) => {
+                let labels: Vec<String> = prs
+                    .iter()
+                    .map(|pr| format!("{}: {}", pr.repository, pr.title))
+                    .collect();
+                match fuzzy::fuzzy_filter(query, &labels).as_slice() {
+                    [index] => prs.get(*index),
+                    _ => None,
+                }
+            }
+        };
+        let Some(pr) = pr else {
+            eprintln!(
+                "{}",
+                Style::Red.paint(&format!(">> ERROR: No single pr matching '{query}'"))
+            );
+            continue;
+        };
+
+        let mut locked = lock_session(args, false).await?;
+        let acknowledged = locked
+            .prs
+            .get(&pr.id)
+            .is_some_and(|session_pr| session_pr.is_acknowledged());
+        if acknowledged {
+            unacknowledge_review(&mut locked, &pr.id).await?;
+            println!("Unacknowledged '{}'", pr.title);
+        } else {
+            acknowledge_review(&mut locked, &pr.id, None).await?;
+            println!("Acknowledged '{}'", pr.title);
+        }
+        toggled = Some(locked);
+    }
+}
+
+/// Reads pr ids from stdin, one per line. Porcelain lines are cut down to the id they start with.
+fn read_pr_ids() -> io::Result<Vec<PullRequestId>> {
+    let mut pr_ids = Vec::new();
+    for line in io::stdin().lines() {
+        let line = line?;
+        let pr_id = line.split('\t').next().unwrap_or_default().trim();
+        if !pr_id.is_empty() {
+            pr_ids.push(pr_id.to_string());
+        }
+    }
+
+    Ok(pr_ids)
+}
+
+/// Prints what acknowledging, or unacknowledging when `acknowledge` is false, would change.
+fn print_acknowledgement_preview(preview: &AcknowledgementPreview, acknowledge: bool) {
+    let pr = &preview.pr;
+    let name = match pr.number {
+        Some(number) => format!("{}#{number}", pr.repository),
+        None => pr.repository.clone(),
+    };
+    if preview.acknowledged == acknowledge {
+        println!("{name} {} would stay as it is", pr.title);
+        return;
+    }
+
+    let (operation, effect) = if acknowledge {
+        ("acknowledge", "hiding")
+    } else {
+        ("unacknowledge", "listing again")
+    };
+    println!(
+        "Would {operation} {name} {}, {effect} {} reviews",
+        pr.title,
+        preview.reviews.len()
+    );
+    for review in &preview.reviews {
+        println!(
+            "  {} by {} at {}",
+            review.state,
+            review.author.login,
+            review
+                .submitted_at
+                .with_timezone(&Local)
+                .format("%Y-%m-%d %H:%M")
+        );
+    }
+}
+
+/// Resolves a pr given on the command line as either an index into `prs` or a pr id,
+/// falling back to the interactive prompt when no selector is given.
+fn select_pr_or_exit(
+    prs: &[GithubPRStatus],
+    selector: Option<&str>,
+    options: TableOptions,
+) -> String {
+    let Some(selector) = selector else {
+        match select_pr(prs, options) {
+            Some(pr_id) => return pr_id,
+            None => {
+                eprintln!("> No prs <");
+                std::process::exit(0);
+            }
+        }
+    };
+
+    let pr = match str::parse::<usize>(selector) {
+        Ok(index) => prs.get(index),
+        Err(_) => prs.iter().find(|pr| pr.id == selector),
+    };
+
+    match pr {
+        Some(pr) => pr.id.clone(),
+        None => {
+            eprintln!(
+                "{}",
+                Style::Red.paint(&format!(">> ERROR: No pr matching '{selector}'"))
+            );
+            std::process::exit(1);
+        }
+    }
+}
+
+fn main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     smol::block_on(_main())
 }
 
-async fn _main() -> Result<(), Box<dyn std::error::Error>> {
+async fn _main() -> Result<ExitCode, Box<dyn std::error::Error>> {
     let args = Args::parse();
+    let mut exit_code = ExitCode::SUCCESS;
+    color::init(args.color);
+    logging::init(match (args.quiet, args.verbose) {
+        (true, _) => Some(logging::Level::Error),
+        (false, 0) => None,
+        (false, 1) => Some(logging::Level::Info),
+        (false, _) => Some(logging::Level::Debug),
+    });
 
-    let mut session = load_session(&args)?;
+    if let Command::Completions { shell } = args.command {
+        print!(
+            "{}",
+            completions::generate(shell, &Args::command(), env!("CARGO_BIN_NAME"))
+        );
+        return Ok(exit_code);
+    }
 
-    if args.force {
-        session.force_update_session_prs();
+    if let Command::Config { ref command } = args.command {
+        let result = config::run_config_command(
+            command,
+            &session_config_path(&args),
+            profile(&args).as_deref(),
+        )
+        .await;
+        // Config mistakes are the user's to fix, a backtrace would only bury the message
+        if let Err(e) = result {
+            eprintln!("Error: {e}");
+            exit_code = ExitCode::FAILURE;
+        }
+        return Ok(exit_code);
+    }
+
+    if let Command::Init {} = args.command {
+        init::run_init(&session_config_path(&args), &session_state_path(&args)).await?;
+        return Ok(exit_code);
+    }
+
+    if let Command::Doctor {} = args.command {
+        return Ok(doctor::run_doctor(&args).await);
+    }
+
+    if let Command::ServeStdio {} = args.command {
+        serve::serve_stdio(&args).await?;
+        return Ok(exit_code);
+    }
+
+    if let Command::Man {} = args.command {
+        print!("{}", man::render(&Args::command(), env!("CARGO_BIN_NAME")));
+        return Ok(exit_code);
+    }
+
+    if let Command::Record { ref output } = args.command {
+        if args.offline {
+            return Err(anyhow!("Recording needs gh, drop --offline").into());
+        }
+        let session = load_session(&args)?;
+        let fixture = Fixture::record(&GithubClient::new().await?, &session).await?;
+        let output = output.clone().unwrap_or_else(|| fixture_path(&args));
+        save_fixture(&fixture, &output)?;
+
+        let count: usize = fixture.repositories.values().map(Vec::len).sum();
+        println!(
+            "Recorded {count} prs from {} repositories to {}",
+            fixture.repositories.len(),
+            output.display()
+        );
+        return Ok(exit_code);
     }
 
+    // Status bars poll status every few seconds and reports only read, none of them should wait
+    // on another invocation's prompt or pager. Saves replace the state file in one rename, so
+    // reading it without the lock never sees it half written.
     match args.command {
-        Command::Count { json } => {
-            let count = &unacknowledged_prs(&mut session).await?.len();
+        Command::Status { format, tmux } => {
+            let session = match load_session(&args) {
+                Ok(session) => session,
+                Err(e) => {
+                    // Whatever prints here ends up in the status bar
+                    logging::debug!("failed to load session for status", error = e);
+                    return Ok(ExitCode::FAILURE);
+                }
+            };
+            let format = if tmux { StatusFormat::Tmux } else { format };
+            print_status(
+                &sort_prs(cached_unacknowledged_prs(&session), &args),
+                format,
+            );
+            return Ok(exit_code);
+        }
+        Command::History { json } => {
+            let session = load_session(&args)?;
+            let pretty_history = prettyify_history(&session.history);
+
             if json {
+                println!("{}", serde_json::to_string(&pretty_history)?)
+            } else {
+                pager::page(&Table::new(pretty_history).to_string(), !args.no_pager)
+            }
+            return Ok(exit_code);
+        }
+        Command::Stats { json } => {
+            let stats = stats::session_stats(&load_session(&args)?);
+
+            if json {
+                println!("{}", serde_json::to_string(&stats)?)
+            } else {
+                let median_time_to_acknowledge = stats
+                    .median_seconds_to_acknowledge
+                    .map(|seconds| duration::format_duration(chrono::Duration::seconds(seconds)))
+                    .unwrap_or("n/a".to_string());
+
+                println!(
+                    "> Reviews per week <\n{}",
+                    Table::new(stats.reviews_per_week)
+                );
+                println!("> Top reviewers <\n{}", Table::new(stats.top_reviewers));
+                println!("> Repositories <\n{}", Table::new(stats.repositories));
+                let oldest_unacknowledged = stats
+                    .oldest_unacknowledged_seconds
+                    .map(|seconds| duration::format_duration(chrono::Duration::seconds(seconds)))
+                    .unwrap_or("n/a".to_string());
+
+                println!("Median time from review to ack: {median_time_to_acknowledge}");
+                println!(
+                    "Unacknowledged: {}, oldest waiting {oldest_unacknowledged}",
+                    stats.unacknowledged
+                );
+                println!(
+                    "Acks in the last 7 days: {}",
+                    stats.acknowledgements_last_week
+                );
+            }
+            return Ok(exit_code);
+        }
+        _ => {}
+    }
+
+    // Read piped ids before locking, the ghp invocation piping them in may still hold the lock
+    let stdin_pr_ids = match args.command {
+        Command::Ack { stdin: true, .. } => read_pr_ids()?,
+        _ => Vec::new(),
+    };
+
+    // Every arm saves, releasing the lock, before prompting or paging. Arms that change the
+    // session after a prompt lock and load it again, it may have changed in the meantime.
+    let mut session = lock_session(&args, args.force).await?;
+
+    match args.command {
+        Command::Count {
+            json,
+            check,
+            by_repo,
+            ref filter,
+        } => {
+            let prs = unacknowledged_prs(&mut session).await?;
+            let session = session.save(&args)?;
+            let prs = filter.filter(&session).apply(prs);
+            let prs = sort_prs(prs, &args);
+            let count = &prs.len();
+            if check && *count > 0 {
+                exit_code = ExitCode::FAILURE;
+            }
+
+            if by_repo {
+                let counts = count_by_repository(&session, &prs);
+                if json {
+                    println!("{}", serde_json::to_string(&counts)?)
+                } else {
+                    println!("{}", Table::new(counts))
+                }
+            } else if json {
                 println!(
                     "{}",
                     serde_json::to_string::<serde_json::Value>(&json!({
@@ -263,30 +1817,136 @@ async fn _main() -> Result<(), Box<dyn std::error::Error>> {
                 println!("{}", count)
             }
         }
-        Command::Fetch { json } => {
-            let prs = unacknowledged_prs(&mut session).await?;
-            let pretty_prs = prettyify_prs(&prs);
-
-            if json {
-                println!("{}", serde_json::to_string(&pretty_prs)?)
+        Command::Fetch {
+            json,
+            format,
+            porcelain,
+            ready_to_merge,
+            outcomes,
+            since,
+            ref filter,
+            ad_hoc: _,
+        } => {
+            let prs = if ready_to_merge {
+                ready_to_merge_prs(&mut session).await?
             } else {
-                println!("{}", Table::new(pretty_prs))
+                unacknowledged_prs(&mut session).await?
+            };
+            let archived = if outcomes {
+                take_unreported_archived_prs(&mut session)
+            } else {
+                Vec::new()
+            };
+            let session = session.save(&args)?;
+
+            let prs = filter
+                .filter(&session)
+                .apply(prs)
+                .into_iter()
+                .filter(|pr| reviewed_since(&session, pr, since))
+                .collect();
+            let prs = sort_prs(prs, &args);
+
+            if let Some(summary) = outcome_summary(&archived) {
+                eprintln!("{summary}");
+            }
+
+            match format.resolve(json, porcelain) {
+                OutputFormat::Json => {
+                    println!("{}", serde_json::to_string(&prettyify_prs(&prs))?)
+                }
+                OutputFormat::Csv => println!("{}", pr_records(&prs, Delimiter::Comma)),
+                OutputFormat::Tsv => println!("{}", pr_records(&prs, Delimiter::Tab)),
+                OutputFormat::Markdown => println!("{}", markdown(&prs)),
+                OutputFormat::Porcelain => println!("{}", export::porcelain(&prs)),
+                OutputFormat::Table => pager::page(
+                    &pr_table(&prs, table_options(&session, &args)),
+                    !args.no_pager,
+                ),
             }
         }
-        Command::FetchAcked { json } => {
-            let prs = acknowledged_prs(&mut session).await?;
-            let pretty_prs = prettyify_prs(&prs);
+        Command::FetchAcked {
+            json,
+            format,
+            porcelain,
+        } => {
+            let prs = sort_prs(acknowledged_prs(&mut session).await?, &args);
+            let session = session.save(&args)?;
+            let pretty_prs = prettyify_acknowledged_prs(&session, &prs);
 
-            if json {
-                println!("{}", serde_json::to_string(&pretty_prs)?)
+            match format.resolve(json, porcelain) {
+                OutputFormat::Json => println!("{}", serde_json::to_string(&pretty_prs)?),
+                OutputFormat::Csv => println!(
+                    "{}",
+                    acknowledged_pr_records(&session, &prs, Delimiter::Comma)
+                ),
+                OutputFormat::Tsv => println!(
+                    "{}",
+                    acknowledged_pr_records(&session, &prs, Delimiter::Tab)
+                ),
+                OutputFormat::Markdown => println!("{}", markdown(&prs)),
+                OutputFormat::Porcelain => println!("{}", export::porcelain(&prs)),
+                OutputFormat::Table => pager::page(
+                    &render_pr_rows(
+                        &prs,
+                        pretty_prs,
+                        |pretty_pr| pretty_pr.status.num,
+                        table_options(&session, &args),
+                    ),
+                    !args.no_pager,
+                ),
+            }
+        }
+        Command::Ack {
+            ref note,
+            dry_run,
+            stdin: true,
+        } => {
+            let (mut acknowledged, mut failed) = (0, 0);
+            for pr_id in &stdin_pr_ids {
+                let result = if dry_run {
+                    preview_acknowledgement(&mut session, pr_id)
+                        .await
+                        .map(|preview| print_acknowledgement_preview(&preview, true))
+                } else {
+                    acknowledge_review(&mut session, pr_id, note.clone()).await
+                };
+                match result {
+                    Ok(()) => acknowledged += 1,
+                    Err(e) => {
+                        eprintln!("Got error while acking {pr_id}: {e}");
+                        failed += 1;
+                    }
+                }
+            }
+            // Dropping the session unsaved leaves the state as it was
+            if !dry_run {
+                session.save(&args)?;
+            }
+
+            let verb = if dry_run {
+                "Would acknowledge"
             } else {
-                println!("{}", Table::new(pretty_prs))
+                "Acknowledged"
+            };
+            println!("{verb} {acknowledged} prs, {failed} failed");
+            if failed > 0 {
+                exit_code = ExitCode::FAILURE;
             }
         }
-        Command::Ack {} => {
-            let prs = unacknowledged_prs(&mut session).await?;
+        Command::Ack {
+            ref note,
+            dry_run,
+            stdin: false,
+        } => {
+            let prs = sort_prs(unacknowledged_prs(&mut session).await?, &args);
+            let session = if dry_run {
+                session.release()
+            } else {
+                session.save(&args)?
+            };
 
-            let pr_id = match select_pr(&prs) {
+            let pr_id = match select_pr(&prs, table_options(&session, &args)) {
                 Some(pr_id) => pr_id,
                 None => {
                     eprintln!("> No prs <");
@@ -294,20 +1954,37 @@ async fn _main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
-            match acknowledge_review(&mut session, &pr_id).await {
+            let mut session = lock_session(&args, false).await?;
+            if dry_run {
+                let preview = preview_acknowledgement(&mut session, &pr_id).await?;
+                print_acknowledgement_preview(&preview, true);
+                return Ok(exit_code);
+            }
+
+            match acknowledge_review(&mut session, &pr_id, note.clone()).await {
                 Ok(_) => {
-                    let prs = unacknowledged_prs(&mut session).await?;
-                    println!("\n> Now <\n{}", Table::new(prettyify_prs(&prs)))
+                    let prs = sort_prs(unacknowledged_prs(&mut session).await?, &args);
+                    let session = session.save(&args)?;
+                    println!(
+                        "\n> Now <\n{}",
+                        pr_table(&prs, table_options(&session, &args))
+                    )
                 }
                 Err(e) => {
+                    session.save(&args)?;
                     eprintln!("Got error while acking: {e}");
                 }
             }
         }
-        Command::Unack {} => {
-            let prs = acknowledged_prs(&mut session).await?;
+        Command::Unack { dry_run } => {
+            let prs = sort_prs(acknowledged_prs(&mut session).await?, &args);
+            let session = if dry_run {
+                session.release()
+            } else {
+                session.save(&args)?
+            };
 
-            let pr_id = match select_pr(&prs) {
+            let pr_id = match select_pr(&prs, table_options(&session, &args)) {
                 Some(pr_id) => pr_id,
                 None => {
                     eprintln!("> No prs <");
@@ -315,22 +1992,280 @@ async fn _main() -> Result<(), Box<dyn std::error::Error>> {
                 }
             };
 
+            let mut session = lock_session(&args, false).await?;
+            if dry_run {
+                let preview = preview_acknowledgement(&mut session, &pr_id).await?;
+                print_acknowledgement_preview(&preview, false);
+                return Ok(exit_code);
+            }
+
             match unacknowledge_review(&mut session, &pr_id).await {
                 Ok(_) => {
-                    let prs = acknowledged_prs(&mut session).await?;
-                    println!("\n> Now <\n{}", Table::new(prettyify_prs(&prs)))
+                    let prs = sort_prs(acknowledged_prs(&mut session).await?, &args);
+                    let session = session.save(&args)?;
+                    println!(
+                        "\n> Now <\n{}",
+                        pr_table(&prs, table_options(&session, &args))
+                    )
                 }
                 Err(e) => {
+                    session.save(&args)?;
                     eprintln!("Got error while unacking: {e}");
                 }
             }
         }
-        Command::ClearSession {} => {
+        Command::Snooze { index, duration } => {
+            let prs = sort_prs(unacknowledged_prs(&mut session).await?, &args);
+            let session = session.save(&args)?;
+
+            let pr_id = match index {
+                Some(index) => match prs.get(index) {
+                    Some(pr) => pr.id.clone(),
+                    None => {
+                        eprintln!(
+                            "{}",
+                            Style::Red.paint(&format!(">> ERROR: Invalid index {index}"))
+                        );
+                        std::process::exit(1);
+                    }
+                },
+                None => match select_pr(&prs, table_options(&session, &args)) {
+                    Some(pr_id) => pr_id,
+                    None => {
+                        eprintln!("> No prs <");
+                        std::process::exit(0);
+                    }
+                },
+            };
+
+            let snoozed_until = duration::from_now(duration)?;
+            let mut session = lock_session(&args, false).await?;
+            let result = snooze_review(&mut session, &pr_id, snoozed_until).await;
+            session.save(&args)?;
+            match result {
+                Ok(_) => {
+                    let snoozed_until: DateTime<Local> = snoozed_until.into();
+                    println!("Snoozed until {snoozed_until}");
+                }
+                Err(e) => {
+                    eprintln!("Got error while snoozing: {e}");
+                }
+            }
+        }
+        Command::Ignore { ref pr } => {
+            let prs = sort_prs(unacknowledged_prs(&mut session).await?, &args);
+            let session = session.save(&args)?;
+            let pr_id = match pr {
+                Some(selector) if str::parse::<usize>(selector).is_err() => selector.clone(),
+                pr => select_pr_or_exit(&prs, pr.as_deref(), table_options(&session, &args)),
+            };
+
+            let mut session = lock_session(&args, false).await?;
+            let result = set_ignored(&mut session, &pr_id, true).await;
+            session.save(&args)?;
+            if let Err(e) = result {
+                eprintln!("Got error while ignoring: {e}");
+            }
+        }
+        Command::Stale { older_than, json } => {
+            let older_than = older_than.unwrap_or(session.stale_after());
+            let prs = stale_prs(&mut session, older_than).await?;
+            let session = session.save(&args)?;
+
+            if json {
+                println!("{}", serde_json::to_string(&prettyify_prs(&prs))?)
+            } else {
+                let options = TableOptions {
+                    stale_after: older_than,
+                    ..table_options(&session, &args)
+                };
+                pager::page(&pr_table(&prs, options), !args.no_pager)
+            }
+        }
+        Command::Ignored { json } => {
+            let prs = sort_prs(ignored_prs(&mut session).await?, &args);
+            let session = session.save(&args)?;
+
+            if json {
+                println!("{}", serde_json::to_string(&prettyify_prs(&prs))?)
+            } else {
+                pager::page(
+                    &pr_table(&prs, table_options(&session, &args)),
+                    !args.no_pager,
+                )
+            }
+        }
+        Command::Unignore { ref pr } => {
+            let prs = sort_prs(ignored_prs(&mut session).await?, &args);
+            let session = session.save(&args)?;
+            let pr_id = select_pr_or_exit(&prs, pr.as_deref(), table_options(&session, &args));
+
+            let mut session = lock_session(&args, false).await?;
+            match set_ignored(&mut session, &pr_id, false).await {
+                Ok(_) => {
+                    let prs = sort_prs(ignored_prs(&mut session).await?, &args);
+                    let session = session.save(&args)?;
+                    println!(
+                        "\n> Now <\n{}",
+                        pr_table(&prs, table_options(&session, &args))
+                    )
+                }
+                Err(e) => {
+                    session.save(&args)?;
+                    eprintln!("Got error while unignoring: {e}");
+                }
+            }
+        }
+        Command::Mute {
+            ref repository,
+            duration,
+        } => {
+            let muted_until = duration::from_now(duration)?;
+            let result = mute_repository(&mut session, repository, muted_until).await;
+            session.save(&args)?;
+            match result {
+                Ok(_) => {
+                    let muted_until: DateTime<Local> = muted_until.into();
+                    println!("Muted {repository} until {muted_until}");
+                }
+                Err(e) => {
+                    eprintln!("Got error while muting: {e}");
+                }
+            }
+        }
+        Command::Unmute { ref repository } => {
+            let result = unmute_repository(&mut session, repository).await;
+            session.save(&args)?;
+            if let Err(e) = result {
+                eprintln!("Got error while unmuting: {e}");
+            }
+        }
+        Command::Archived { json } => {
+            let pretty_prs = prettyify_archived_prs(&archived_prs(&mut session).await?);
+            session.save(&args)?;
+
+            if json {
+                println!("{}", serde_json::to_string(&pretty_prs)?)
+            } else {
+                pager::page(&Table::new(pretty_prs).to_string(), !args.no_pager)
+            }
+        }
+        Command::Summary { json } => {
+            let unacknowledged = unacknowledged_prs(&mut session).await?;
+            let acknowledged = acknowledged_prs(&mut session).await?;
+            let session = session.save(&args)?;
+            let summaries = stats::repository_summaries(&session, &unacknowledged, &acknowledged);
+
+            if json {
+                println!("{}", serde_json::to_string(&summaries)?)
+            } else {
+                println!("{}", Table::new(summaries))
+            }
+        }
+        Command::Triage {} => triage(session, &args).await?,
+        Command::Yank { ref pr } => {
+            let prs = sort_prs(unacknowledged_prs(&mut session).await?, &args);
+            let session = session.save(&args)?;
+            let pr_id = select_pr_or_exit(&prs, pr.as_deref(), table_options(&session, &args));
+            let Some(url) = prs
+                .iter()
+                .find(|pr| pr.id == pr_id)
+                .and_then(GithubPRStatus::web_url)
+            else {
+                return Err(anyhow!("No url known for pr {pr_id}").into());
+            };
+
+            clipboard::copy(&url)?;
+            println!("Copied {url}");
+        }
+        Command::Undo {} => {
+            let result = undo_last_operation(&mut session).await;
+            let session = session.save(&args)?;
+            match result {
+                Ok(entry) => {
+                    let title = session
+                        .prs
+                        .get(&entry.pr_id)
+                        .map(|pr| GithubPRStatus::from(pr).title)
+                        .unwrap_or(entry.pr_id);
+                    println!("Undid {} of '{title}'", entry.operation);
+                }
+                Err(e) => {
+                    eprintln!("Got error while undoing: {e}");
+                }
+            }
+        }
+        Command::ClearSession { dry_run } => {
+            if dry_run {
+                let acknowledged = session
+                    .prs
+                    .values()
+                    .filter(|pr| pr.is_acknowledged())
+                    .count();
+                println!(
+                    "Would forget {} tracked prs, {acknowledged} of them acknowledged, and {} undoable operations",
+                    session.prs.len(),
+                    session.journal.len()
+                );
+                return Ok(exit_code);
+            }
             clear_session(&mut session).await;
+            session.save(&args)?;
         }
-    };
+        Command::Repo { ref command } => match command {
+            RepoCommand::Add { repository } => {
+                config::add_repository(
+                    &session_config_path(&args),
+                    profile(&args).as_deref(),
+                    repository,
+                )?;
+                session.repositories.insert(repository.clone());
+                session.force_update_session_prs();
+                update_offline_session_prs(&mut session, &args).await?;
+                session.update_session_prs().await?;
+                let session = session.save(&args)?;
 
-    save_session(&session, &args)?;
+                let count = session
+                    .prs
+                    .values()
+                    .filter(|pr| pr.pr().repository == *repository)
+                    .count();
+                println!("Now tracking {repository}, found {count} prs");
+            }
+            RepoCommand::Rm { repository } => {
+                config::remove_repository(
+                    &session_config_path(&args),
+                    profile(&args).as_deref(),
+                    repository,
+                )?;
+                session.repositories.remove(repository);
+                session
+                    .prs
+                    .retain(|_, pr| pr.pr().repository != *repository);
+                session.save(&args)?;
+                println!("No longer tracking {repository}");
+            }
+            RepoCommand::List {} => {
+                let mut repositories: Vec<&String> = session.repositories.iter().collect();
+                repositories.sort();
+                for repository in repositories {
+                    println!("{repository}");
+                }
+            }
+        },
+        Command::Completions { .. }
+        | Command::Man {}
+        | Command::Config { .. }
+        | Command::Init {}
+        | Command::Doctor {}
+        | Command::ServeStdio {}
+        | Command::Record { .. }
+        | Command::Status { .. }
+        | Command::History { .. }
+        | Command::Stats { .. } => {
+            unreachable!("handled before loading the session")
+        }
+    };
 
-    Ok(())
+    Ok(exit_code)
 }
@@ -0,0 +1,199 @@
+use clap::{Arg, Command};
+
+const ENVIRONMENT: &[(&str, &str)] = &[
+    (
+        "GHPRS_CONFIG_FILE",
+        "Path to the config file, overridden by --session-config-path.",
+    ),
+    (
+        "GHPRS_STATE_FILE",
+        "Path to the session state file, overridden by --session-state-path.",
+    ),
+    (
+        "GHPRS_LOG",
+        "Most verbose diagnostics to print to stderr: error, warn, info or debug. Defaults to warn.",
+    ),
+    (
+        "GHPRS_LOG_FORMAT",
+        "Set to json to print diagnostics as one JSON object per line.",
+    ),
+    (
+        "GHPRS_GH_RECORD",
+        "Path to append every gh invocation and its output to, one JSON object per line.",
+    ),
+    (
+        "GHPRS_GH_REPLAY",
+        "Path of a recording made with GHPRS_GH_RECORD to answer gh invocations from instead of running gh.",
+    ),
+    (
+        "GHPRS_CLIPBOARD",
+        "Shell command the yank command pipes urls to, defaults to pbcopy on macOS, clip on Windows and the first of wl-copy, xclip or xsel found elsewhere.",
+    ),
+    (
+        "GHPRS_PROFILE",
+        "Named profile to use, overridden by --profile.",
+    ),
+    (
+        "GHPRS_PAGER",
+        "Pager long listings are piped through when stdout is a terminal, takes precedence over PAGER. Set to cat or an empty string to print directly.",
+    ),
+    (
+        "PAGER",
+        "Pager used when GHPRS_PAGER is unset, defaults to less. less is run with LESS=FRX unless LESS is set.",
+    ),
+    (
+        "NO_COLOR",
+        "Disables colored output when set to a non-empty value, only consulted with the default --color auto.",
+    ),
+    (
+        "XDG_CONFIG_HOME",
+        "Directory holding the default config file, defaults to ~/.config, %APPDATA%\\ghprs on Windows and ~/Library/Application Support/ghprs on macOS.",
+    ),
+    (
+        "XDG_STATE_HOME",
+        "Directory holding the default session state files, defaults to ~/.local/state, %LOCALAPPDATA%\\ghprs on Windows and ~/Library/Application Support/ghprs on macOS. State found in XDG_CONFIG_HOME by older versions is moved here.",
+    ),
+];
+
+const CONFIG_KEYS: &[(&str, &str)] = &[
+    ("author", "GitHub login whose pull requests are tracked."),
+    (
+        "repositories",
+        "List of repositories to track, in owner/name form. An entry may instead be a table like { name = \"org/repo\", author = \"my-bot\" }, or a [[repositories]] table, to track a different author in that repository.",
+    ),
+    (
+        "session_state_file",
+        "Optional path to the session state file.",
+    ),
+    (
+        "fixture_file",
+        "Optional path the record command writes to and --offline reads from, defaults to ghprs-fixture.json in the state directory.",
+    ),
+    (
+        "ack_ttl",
+        "Optional number of days after which an acknowledgement lapses back to unacknowledged.",
+    ),
+    (
+        "stale_after",
+        "Optional number of days after which an unanswered review is highlighted as stale, defaults to 7.",
+    ),
+    (
+        "required_reviewers",
+        "Optional list of logins whose reviews count as actionable feedback. Reviews from anyone else are still listed but don't mark a pr as needing attention or reset its acknowledgement.",
+    ),
+    (
+        "base_branch",
+        "Optional branch name, fetch and count only list prs targeting it unless given --base or --any-base.",
+    ),
+    (
+        "archive_retention",
+        "Optional number of days merged and closed prs are kept for the archived command, defaults to 30.",
+    ),
+    (
+        "profiles",
+        "Optional [profiles.<name>] tables selected with --profile, each may override author, repositories, session_state_file, fixture_file, ack_ttl, stale_after, required_reviewers, base_branch and archive_retention. A profile's state defaults to ghprs-state-<name>.json.",
+    ),
+];
+
+fn escape(s: &str) -> String {
+    let escaped = s.replace('\\', "\\e").replace('-', "\\-");
+    if escaped.starts_with('.') || escaped.starts_with('\'') {
+        format!("\\&{escaped}")
+    } else {
+        escaped
+    }
+}
+
+fn arg_synopsis(arg: &Arg) -> String {
+    let value = arg
+        .get_value_names()
+        .and_then(|names| names.first())
+        .map(|name| name.to_string())
+        .unwrap_or_else(|| arg.get_id().to_string().to_uppercase());
+
+    if arg.is_positional() {
+        return format!("\\fI{}\\fR", escape(&value));
+    }
+
+    let mut names = Vec::new();
+    if let Some(short) = arg.get_short() {
+        names.push(format!("\\fB\\-{short}\\fR"));
+    }
+    if let Some(long) = arg.get_long() {
+        names.push(format!("\\fB\\-\\-{}\\fR", escape(long)));
+    }
+
+    let mut synopsis = names.join(", ");
+    if arg.get_action().takes_values() {
+        synopsis += &format!(" \\fI{}\\fR", escape(&value));
+    }
+    synopsis
+}
+
+fn arguments(command: &Command) -> String {
+    let mut page = String::new();
+
+    for arg in command.get_arguments() {
+        page += &format!(".TP\n{}\n", arg_synopsis(arg));
+        if let Some(help) = arg.get_help() {
+            page += &format!("{}\n", escape(&help.to_string()));
+        }
+
+        let values: Vec<String> = arg
+            .get_possible_values()
+            .iter()
+            .filter(|_| arg.get_action().takes_values())
+            .map(|value| value.get_name().to_string())
+            .collect();
+        if !values.is_empty() {
+            page += &format!("Possible values: {}.\n", escape(&values.join(", ")));
+        }
+    }
+
+    page
+}
+
+/// Renders a roff man page for `command`, including its subcommands, environment and config keys.
+pub fn render(command: &Command, bin_name: &str) -> String {
+    let mut page = format!(
+        ".TH {} 1\n.SH NAME\n{} \\- track acknowledgement of GitHub pull request reviews\n",
+        escape(&bin_name.to_uppercase()),
+        escape(bin_name)
+    );
+
+    page += &format!(
+        ".SH SYNOPSIS\n\\fB{}\\fR [\\fIOPTIONS\\fR] \\fICOMMAND\\fR\n",
+        escape(bin_name)
+    );
+    page += ".SH DESCRIPTION\nLists pull requests authored by the configured author that have received reviews, and lets reviews be acknowledged so only new feedback is surfaced.\n";
+
+    page += ".SH OPTIONS\n";
+    page += &arguments(command);
+
+    page += ".SH COMMANDS\n";
+    for subcommand in command.get_subcommands() {
+        let aliases: Vec<&str> = subcommand.get_all_aliases().collect();
+        page += &format!(".SS {}\n", escape(subcommand.get_name()));
+        if let Some(about) = subcommand.get_about() {
+            page += &format!("{}\n", escape(&about.to_string()));
+        }
+        if !aliases.is_empty() {
+            page += &format!(".PP\nAliases: {}\n", escape(&aliases.join(", ")));
+        }
+        page += &arguments(subcommand);
+    }
+
+    page += ".SH ENVIRONMENT\n";
+    for (name, description) in ENVIRONMENT {
+        page += &format!(".TP\n\\fB{}\\fR\n{}\n", escape(name), escape(description));
+    }
+
+    page += ".SH CONFIGURATION\nThe config file is TOML and supports the following keys.\n";
+    for (key, description) in CONFIG_KEYS {
+        page += &format!(".TP\n\\fB{}\\fR\n{}\n", escape(key), escape(description));
+    }
+
+    page += ".SH FILES\n.TP\n\\fI$XDG_CONFIG_HOME/ghprs.toml\\fR\nDefault config file.\n.TP\n\\fI$XDG_STATE_HOME/ghprs\\-state.json\\fR\nDefault session state file.\n";
+
+    page
+}
@@ -0,0 +1,96 @@
+use anyhow::{anyhow, bail};
+use serde_json::Value;
+
+use crate::prs::SessionState;
+
+/// Version written by this build, bump it and append to `MIGRATIONS` when the schema changes.
+pub const STATE_VERSION: u32 = 1;
+
+/// `MIGRATIONS[n]` upgrades a state document from version `n` to `n + 1`.
+const MIGRATIONS: &[fn(&mut Value) -> anyhow::Result<()>] = &[from_unversioned];
+
+/// Unversioned state only ever gained fields with serde defaults, so it needs no rewriting.
+fn from_unversioned(_state: &mut Value) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Parses a state file of any known version, upgrading it to `STATE_VERSION`.
+/// Returns the state along with the version it was written with.
+pub fn parse_state(contents: &str) -> anyhow::Result<(SessionState, u32)> {
+    let mut state: Value = serde_json::from_str(contents)?;
+    let Some(object) = state.as_object_mut() else {
+        bail!("Expected a JSON object")
+    };
+
+    let version = match object.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .and_then(|version| u32::try_from(version).ok())
+            .ok_or_else(|| anyhow!("Invalid state version {version}"))?,
+    };
+    if version > STATE_VERSION {
+        bail!("State version {version} was written by a newer ghp, this one supports up to {STATE_VERSION}")
+    }
+
+    for (from_version, migration) in MIGRATIONS.iter().enumerate().skip(version as usize) {
+        migration(&mut state)
+            .map_err(|e| anyhow!("Failed to migrate state from version {from_version}: {e}"))?;
+    }
+    state["version"] = STATE_VERSION.into();
+
+    Ok((serde_json::from_value(state)?, version))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn unversioned_state_is_upgraded() {
+        let contents = json!({
+            "last_fetch_time": null,
+            "prs": {},
+        })
+        .to_string();
+
+        let (state, version) = parse_state(&contents).unwrap();
+
+        assert_eq!(version, 0);
+        assert_eq!(state.version, STATE_VERSION);
+        assert!(state.journal.is_empty());
+        assert!(state.fetch_watermarks.is_empty());
+    }
+
+    #[test]
+    fn current_state_is_read_as_is() {
+        let contents = serde_json::to_string(&SessionState::default()).unwrap();
+
+        let (state, version) = parse_state(&contents).unwrap();
+
+        assert_eq!(version, STATE_VERSION);
+        assert_eq!(state.version, STATE_VERSION);
+    }
+
+    #[test]
+    fn newer_state_is_rejected() {
+        let contents = json!({
+            "version": STATE_VERSION + 1,
+            "last_fetch_time": null,
+            "prs": {},
+        })
+        .to_string();
+
+        let error = parse_state(&contents).unwrap_err();
+
+        assert!(error.to_string().contains("newer ghp"), "{error}");
+    }
+
+    #[test]
+    fn malformed_state_is_rejected() {
+        assert!(parse_state("[]").is_err());
+        assert!(parse_state(r#"{"version": "one", "prs": {}}"#).is_err());
+        assert!(parse_state(r#"{"version": -1, "prs": {}}"#).is_err());
+    }
+}
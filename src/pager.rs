@@ -0,0 +1,44 @@
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    process::{Command, Stdio},
+};
+
+/// Prints `output` through `$PAGER` when stdout is a terminal, like git does.
+///
+/// `less` is run with `LESS=FRX` unless the user set `LESS`, so output that fits on one screen is
+/// printed directly rather than opening the pager.
+pub fn page(output: &str, enabled: bool) {
+    if !enabled || !io::stdout().is_terminal() {
+        println!("{output}");
+        return;
+    }
+
+    let pager = env::var("GHPRS_PAGER")
+        .or_else(|_| env::var("PAGER"))
+        .unwrap_or("less".to_string());
+    if pager.is_empty() || pager == "cat" {
+        println!("{output}");
+        return;
+    }
+
+    let mut command = Command::new("sh");
+    command.arg("-c").arg(&pager).stdin(Stdio::piped());
+    if env::var_os("LESS").is_none() {
+        command.env("LESS", "FRX");
+    }
+
+    let mut child = match command.spawn() {
+        Ok(child) => child,
+        Err(_) => {
+            println!("{output}");
+            return;
+        }
+    };
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // The pager may exit before reading everything, a broken pipe is expected then.
+        let _ = writeln!(stdin, "{output}");
+    }
+    let _ = child.wait();
+}
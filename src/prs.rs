@@ -1,19 +1,65 @@
-use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
+use std::{
+    collections::{HashMap, HashSet},
+    time::Instant,
+};
 
 use crate::{
-    gh_client::{GithubClient, GithubClientError},
-    GithubPRStatus,
+    gh_client::{
+        GithubClient, GithubClientError, GithubPRReview, GithubPRState, GithubPRStatus, PrFetcher,
+    },
+    logging,
+    migrations::STATE_VERSION,
 };
 use chrono::{DateTime, Duration, Utc};
+use thiserror::Error;
 
 pub type PullRequestId = String;
 
+#[derive(Error, Debug)]
+pub enum SessionError {
+    #[error(transparent)]
+    Github(#[from] GithubClientError),
+    #[error("Could not find PR with ID: {0}")]
+    UnknownPr(PullRequestId),
+    #[error("Repository {0} is not configured")]
+    UnknownRepository(String),
+    #[error("Repository {0} is not muted")]
+    NotMuted(String),
+    #[error("No acknowledgement operations to undo")]
+    NothingToUndo,
+    #[error("PR with ID {0} is no longer tracked, cannot undo")]
+    NoLongerTracked(PullRequestId),
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct SessionPr {
     acknowledged: bool,
     pr: GithubPRStatus,
+    #[serde(default)]
+    snoozed_until: Option<DateTime<Utc>>,
+    #[serde(default)]
+    acknowledged_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    ignored: bool,
+    #[serde(default)]
+    note: Option<String>,
+}
+
+impl SessionPr {
+    pub fn pr(&self) -> &GithubPRStatus {
+        &self.pr
+    }
+
+    pub fn is_acknowledged(&self) -> bool {
+        self.acknowledged
+    }
+
+    fn is_snoozed(&self) -> bool {
+        self.snoozed_until
+            .map(|snoozed_until| snoozed_until > Utc::now())
+            .unwrap_or(false)
+    }
 }
 
 impl From<&SessionPr> for GithubPRStatus {
@@ -26,12 +72,118 @@ impl From<&SessionPr> for GithubPRStatus {
 pub struct SessionConfig {
     pub author: String,
     pub repositories: HashSet<String>,
+    /// Authors tracked in specific repositories instead of `author`.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub repository_authors: HashMap<String, String>,
+    /// Number of days after which an acknowledgement lapses back to unacknowledged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ack_ttl: Option<u32>,
+    /// Number of days after which an unanswered review counts as stale.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub stale_after: Option<u32>,
+    /// Only reviews from these logins reset acknowledgement, every review counts if unset.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub required_reviewers: Option<HashSet<String>>,
+    /// Only list prs targeting this branch unless a listing asks for another one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base_branch: Option<String>,
+    /// Number of days merged and closed prs stay in the archive.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub archive_retention: Option<u32>,
 }
 
-#[derive(Debug, Default, Serialize, Deserialize)]
+/// A pr that was merged or closed, kept with its acknowledgement after leaving the open list.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ArchivedPr {
+    pub pr: GithubPRStatus,
+    pub state: GithubPRState,
+    pub archived_at: DateTime<Utc>,
+    pub acknowledged: bool,
+    #[serde(default)]
+    pub note: Option<String>,
+    /// Whether `take_unreported_archived_prs` already returned this pr.
+    #[serde(default)]
+    pub reported: bool,
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum AcknowledgementOperation {
+    Acknowledge,
+    Unacknowledge,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub pr_id: PullRequestId,
+    pub operation: AcknowledgementOperation,
+    pub previously_acknowledged: bool,
+    /// When the pr was acknowledged before the operation, restored by undo so `ack_ttl` keeps
+    /// counting from the original ack.
+    #[serde(default)]
+    pub previously_acknowledged_at: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub previous_note: Option<String>,
+}
+
+impl std::fmt::Display for AcknowledgementOperation {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            AcknowledgementOperation::Acknowledge => write!(f, "ack"),
+            AcknowledgementOperation::Unacknowledge => write!(f, "unack"),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    pub time: DateTime<Utc>,
+    pub operation: AcknowledgementOperation,
+    pub pr: GithubPRStatus,
+}
+
+/// Minutes a fetch is reused for before listings contact github again.
+pub const FETCH_INTERVAL_MINUTES: i64 = 5;
+/// Maximum number of ack/unack operations remembered for `undo`.
+const MAX_JOURNAL_ENTRIES: usize = 32;
+/// Days a review can go unanswered before it counts as stale when `stale_after` isn't configured.
+const DEFAULT_STALE_AFTER_DAYS: u32 = 7;
+/// Maximum number of ack/unack operations kept in the audit history.
+const MAX_HISTORY_ENTRIES: usize = 500;
+/// Days merged and closed prs are archived for when `archive_retention` isn't configured.
+const DEFAULT_ARCHIVE_RETENTION_DAYS: u32 = 30;
+/// Minutes between full fetches. Incremental fetches in between only see prs that were updated,
+/// so a pr that stops matching without an update is only noticed by a full fetch.
+const FULL_FETCH_INTERVAL_MINUTES: i64 = 60;
+/// How far before the start of a fetch a watermark is placed when no fetched pr is newer, to
+/// cover the difference between the local clock and github's.
+const WATERMARK_CLOCK_SKEW_MINUTES: i64 = 5;
+
+/// How far a repository's prs have been fetched, later fetches only ask for prs updated since.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct FetchWatermark {
+    /// Author the prs were fetched for, the watermark doesn't apply to other authors.
+    pub author: String,
+    pub updated_at: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
 pub struct SessionState {
+    /// Schema version, see `migrations` for how older versions are upgraded.
+    pub version: u32,
     pub last_fetch_time: Option<DateTime<Utc>>,
     pub prs: HashMap<PullRequestId, SessionPr>,
+    #[serde(default)]
+    pub journal: Vec<JournalEntry>,
+    #[serde(default)]
+    pub history: Vec<HistoryEntry>,
+    #[serde(default)]
+    pub muted_repositories: HashMap<String, DateTime<Utc>>,
+    #[serde(default)]
+    pub archived: HashMap<PullRequestId, ArchivedPr>,
+    #[serde(default)]
+    pub fetch_watermarks: HashMap<String, FetchWatermark>,
+    #[serde(default)]
+    pub last_full_fetch_time: Option<DateTime<Utc>>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -39,7 +191,35 @@ pub struct Session {
     pub prs: HashMap<PullRequestId, SessionPr>,
     pub author: String,
     pub repositories: HashSet<String>,
+    pub repository_authors: HashMap<String, String>,
+    pub ack_ttl: Option<u32>,
+    pub stale_after: Option<u32>,
+    pub required_reviewers: Option<HashSet<String>>,
+    pub base_branch: Option<String>,
+    pub archive_retention: Option<u32>,
     pub last_fetch_time: Option<DateTime<Utc>>,
+    pub journal: Vec<JournalEntry>,
+    pub history: Vec<HistoryEntry>,
+    pub muted_repositories: HashMap<String, DateTime<Utc>>,
+    pub archived: HashMap<PullRequestId, ArchivedPr>,
+    pub fetch_watermarks: HashMap<String, FetchWatermark>,
+    pub last_full_fetch_time: Option<DateTime<Utc>>,
+}
+
+impl Default for SessionState {
+    fn default() -> Self {
+        SessionState {
+            version: STATE_VERSION,
+            last_fetch_time: None,
+            prs: HashMap::new(),
+            journal: Vec::new(),
+            history: Vec::new(),
+            muted_repositories: HashMap::new(),
+            archived: HashMap::new(),
+            fetch_watermarks: HashMap::new(),
+            last_full_fetch_time: None,
+        }
+    }
 }
 
 impl From<Session> for (SessionConfig, SessionState) {
@@ -48,16 +228,41 @@ impl From<Session> for (SessionConfig, SessionState) {
             prs,
             author,
             repositories,
+            repository_authors,
+            ack_ttl,
+            stale_after,
+            required_reviewers,
+            base_branch,
+            archive_retention,
             last_fetch_time,
+            journal,
+            history,
+            muted_repositories,
+            archived,
+            fetch_watermarks,
+            last_full_fetch_time,
         } = value;
         (
             SessionConfig {
                 author,
                 repositories,
+                repository_authors,
+                ack_ttl,
+                stale_after,
+                required_reviewers,
+                base_branch,
+                archive_retention,
             },
             SessionState {
+                version: STATE_VERSION,
                 last_fetch_time,
                 prs,
+                journal,
+                history,
+                muted_repositories,
+                archived,
+                fetch_watermarks,
+                last_full_fetch_time,
             },
         )
     }
@@ -68,121 +273,533 @@ impl Session {
         let SessionConfig {
             author,
             repositories,
+            repository_authors,
+            ack_ttl,
+            stale_after,
+            required_reviewers,
+            base_branch,
+            archive_retention,
         } = config;
         let SessionState {
+            version: _,
             last_fetch_time,
             prs,
+            journal,
+            history,
+            muted_repositories,
+            archived,
+            fetch_watermarks,
+            last_full_fetch_time,
         } = state;
 
         Session {
             author,
             repositories,
+            repository_authors,
+            ack_ttl,
+            stale_after,
+            required_reviewers,
+            base_branch,
+            archive_retention,
             last_fetch_time,
             prs,
+            journal,
+            history,
+            muted_repositories,
+            archived,
+            fetch_watermarks,
+            last_full_fetch_time,
+        }
+    }
+
+    /// How long a review can go unanswered before it counts as stale, a week unless configured.
+    pub fn stale_after(&self) -> Duration {
+        Duration::days(self.stale_after.unwrap_or(DEFAULT_STALE_AFTER_DAYS).into())
+    }
+
+    /// The reviews of `pr` that count as actionable under `required_reviewers`.
+    pub fn actionable_reviews<'a>(
+        &'a self,
+        pr: &'a GithubPRStatus,
+    ) -> impl Iterator<Item = &'a GithubPRReview> + 'a {
+        pr.reviews.iter().filter(|review| {
+            self.required_reviewers
+                .as_ref()
+                .is_none_or(|required| required.contains(&review.author.login))
+        })
+    }
+
+    /// The newest review that counts as actionable under `required_reviewers`.
+    pub fn latest_actionable_review<'a>(
+        &'a self,
+        pr: &'a GithubPRStatus,
+    ) -> Option<&'a GithubPRReview> {
+        self.actionable_reviews(pr)
+            .max_by_key(|review| review.submitted_at)
+    }
+
+    /// The time of the newest review that counts as actionable under `required_reviewers`.
+    pub fn latest_actionable_review_time(&self, pr: &GithubPRStatus) -> Option<DateTime<Utc>> {
+        self.latest_actionable_review(pr)
+            .map(|review| review.submitted_at)
+    }
+
+    pub fn acknowledgement_note(&self, pr_id: &PullRequestId) -> Option<&str> {
+        self.prs.get(pr_id).and_then(|pr| pr.note.as_deref())
+    }
+
+    fn is_repository_muted(&self, repository: &str) -> bool {
+        self.muted_repositories
+            .get(repository)
+            .map(|muted_until| *muted_until > Utc::now())
+            .unwrap_or(false)
+    }
+
+    /// Journals `operation` on `pr_id` for undo, `previous` being the pr before it was applied.
+    fn record_operation(
+        &mut self,
+        pr_id: &PullRequestId,
+        operation: AcknowledgementOperation,
+        previous: SessionPr,
+    ) {
+        self.journal.push(JournalEntry {
+            pr_id: pr_id.clone(),
+            operation,
+            previously_acknowledged: previous.acknowledged,
+            previously_acknowledged_at: previous.acknowledged_at,
+            previous_note: previous.note,
+        });
+
+        if self.journal.len() > MAX_JOURNAL_ENTRIES {
+            let excess = self.journal.len() - MAX_JOURNAL_ENTRIES;
+            self.journal.drain(..excess);
+        }
+
+        if let Some(session_pr) = self.prs.get(pr_id) {
+            self.history.push(HistoryEntry {
+                time: Utc::now(),
+                operation,
+                pr: session_pr.pr.clone(),
+            });
+        }
+
+        if self.history.len() > MAX_HISTORY_ENTRIES {
+            let excess = self.history.len() - MAX_HISTORY_ENTRIES;
+            self.history.drain(..excess);
         }
     }
 }
 
 impl Session {
-    pub async fn fetch_prs(&self, github_client: &GithubClient) -> Vec<GithubPRStatus> {
+    /// Fetches the prs of every configured repository, repositories that fail are logged and skipped.
+    pub async fn fetch_prs<F: PrFetcher>(&self, fetcher: &F) -> Vec<GithubPRStatus> {
+        self.fetch_repository_prs(fetcher)
+            .await
+            .into_iter()
+            .flat_map(|(_, prs)| prs.into_iter().flatten())
+            .collect()
+    }
+
+    /// The open prs of every configured repository, `None` for repositories that failed.
+    async fn fetch_repository_prs<F: PrFetcher>(
+        &self,
+        fetcher: &F,
+    ) -> Vec<(String, Option<Vec<GithubPRStatus>>)> {
         use futures::future::join_all;
         let Session {
             prs: _,
             author,
             repositories,
+            repository_authors,
+            ack_ttl: _,
+            stale_after: _,
+            required_reviewers: _,
+            base_branch: _,
+            archive_retention: _,
             last_fetch_time: _,
+            journal: _,
+            history: _,
+            muted_repositories: _,
+            archived: _,
+            fetch_watermarks: _,
+            last_full_fetch_time: _,
         } = self;
 
-        let pr_statuses: Vec<Option<Vec<GithubPRStatus>>> =
-            join_all(repositories.iter().map(|repository| async move {
-                let repository_pr_statuses =
-                    match github_client.new_pr_status(repository, Some(author)).await {
-                        Ok(v) => v,
-                        Err(e) => {
-                            eprintln!(
-                        "Encountered error processing statuses for repo {} with for author {}: {}",
-                        &repository, author, e
+        join_all(repositories.iter().map(|repository| async move {
+            let author = repository_authors.get(repository).unwrap_or(author);
+            let started = Instant::now();
+            let repository_pr_statuses = match fetcher.fetch_prs(repository, author).await {
+                Ok(v) => v,
+                Err(e) => {
+                    logging::warn!(
+                        "failed to fetch prs",
+                        repository = repository,
+                        author = author,
+                        error = e
                     );
-                            return None;
-                        }
-                    };
+                    return (repository.clone(), None);
+                }
+            };
+            logging::debug!(
+                "fetched prs",
+                repository = repository,
+                author = author,
+                count = repository_pr_statuses.len(),
+                duration_ms = started.elapsed().as_millis()
+            );
 
-                Some(
-                    repository_pr_statuses
-                        .into_iter()
-                        .map(|repository_pr_status| {
-                            repository_pr_status.convert_to_core(repository.clone())
-                        })
-                        .collect(),
-                )
-            }))
-            .await;
+            let prs = repository_pr_statuses
+                .into_iter()
+                .map(|repository_pr_status| {
+                    repository_pr_status.convert_to_core(repository.clone())
+                })
+                .collect();
+            (repository.clone(), Some(prs))
+        }))
+        .await
+    }
 
-        pr_statuses
-            .into_iter()
-            .flat_map(|p| p.into_iter().flatten())
-            .collect()
+    /// Prs of every configured repository updated since its watermark, `None` when any
+    /// repository can't be fetched incrementally.
+    async fn fetch_updated_repository_prs<F: PrFetcher>(
+        &self,
+        fetcher: &F,
+    ) -> Option<Vec<(String, Vec<(GithubPRStatus, GithubPRState)>)>> {
+        use futures::future::join_all;
+
+        let updates = join_all(self.repositories.iter().map(|repository| async move {
+            let author = self.repository_author(repository);
+            let since = self.fetch_watermarks.get(repository)?.updated_at;
+            match fetcher.fetch_updated_prs(repository, author, since).await {
+                Ok(prs) => Some((repository.clone(), prs?)),
+                Err(e) => {
+                    logging::warn!(
+                        "failed to fetch updated prs",
+                        repository = repository,
+                        author = author,
+                        error = e
+                    );
+                    None
+                }
+            }
+        }))
+        .await;
+
+        updates.into_iter().collect()
     }
 
     pub fn force_update_session_prs(&mut self) {
         self.last_fetch_time = None;
+        self.last_full_fetch_time = None;
     }
 
-    pub async fn update_session_prs(&mut self) -> Result<(), GithubClientError> {
+    /// The author whose prs are tracked in `repository`.
+    pub fn repository_author(&self, repository: &str) -> &str {
+        self.repository_authors
+            .get(repository)
+            .unwrap_or(&self.author)
+    }
+
+    /// Whether the tracked prs can be refreshed from just the prs updated since the last fetch.
+    fn can_fetch_incrementally(&self, now: DateTime<Utc>) -> bool {
+        let full_fetch_is_recent = self
+            .last_full_fetch_time
+            .is_some_and(|last_full_fetch_time| {
+                now.signed_duration_since(last_full_fetch_time)
+                    < Duration::minutes(FULL_FETCH_INTERVAL_MINUTES)
+            });
+        let watermarks_apply = self.repositories.iter().all(|repository| {
+            self.fetch_watermarks
+                .get(repository)
+                .is_some_and(|watermark| watermark.author == self.repository_author(repository))
+        });
+        let prs_are_configured = self
+            .prs
+            .values()
+            .all(|session_pr| self.repositories.contains(&session_pr.pr.repository));
+
+        full_fetch_is_recent && watermarks_apply && prs_are_configured
+    }
+
+    /// Moves the watermark of `repository` to the newest update fetched, or to shortly before
+    /// `fetch_started` if that is later since the fetch saw every update made before it began.
+    fn advance_watermark(
+        &mut self,
+        repository: &str,
+        newest_update: Option<DateTime<Utc>>,
+        fetch_started: DateTime<Utc>,
+    ) {
+        let author = self.repository_author(repository).to_string();
+        let previous = self
+            .fetch_watermarks
+            .get(repository)
+            .filter(|watermark| watermark.author == author)
+            .map(|watermark| watermark.updated_at);
+        let updated_at = [
+            Some(fetch_started - Duration::minutes(WATERMARK_CLOCK_SKEW_MINUTES)),
+            newest_update,
+            previous,
+        ]
+        .into_iter()
+        .flatten()
+        .max()
+        .unwrap_or(fetch_started);
+
+        self.fetch_watermarks.insert(
+            repository.to_string(),
+            FetchWatermark { author, updated_at },
+        );
+    }
+
+    fn expire_acknowledgements(&mut self) {
+        let Some(ack_ttl) = self.ack_ttl else {
+            return;
+        };
+        let now = Utc::now();
+
+        for session_pr in self.prs.values_mut().filter(|pr| pr.acknowledged) {
+            let acknowledged_at = *session_pr.acknowledged_at.get_or_insert(now);
+
+            if now.signed_duration_since(acknowledged_at) > Duration::days(ack_ttl.into()) {
+                session_pr.acknowledged = false;
+                session_pr.acknowledged_at = None;
+            }
+        }
+    }
+
+    /// Expires acknowledgements and mutes, returning whether the prs are due to be refetched.
+    fn prepare_update(&mut self) -> bool {
+        self.expire_acknowledgements();
+
+        let now = Utc::now();
+        self.muted_repositories
+            .retain(|_, muted_until| *muted_until > now);
+        let archive_retention = Duration::days(
+            self.archive_retention
+                .unwrap_or(DEFAULT_ARCHIVE_RETENTION_DAYS)
+                .into(),
+        );
+        self.archived.retain(|_, archived| {
+            now.signed_duration_since(archived.archived_at) < archive_retention
+        });
+
         if let Some(last_fetch_time) = self.last_fetch_time {
             let time_since_last_fetch = Utc::now().signed_duration_since(last_fetch_time);
-            if time_since_last_fetch < Duration::minutes(5) {
-                return Ok(());
+            if time_since_last_fetch < Duration::minutes(FETCH_INTERVAL_MINUTES) {
+                logging::debug!(
+                    "skipped fetch, last fetch is recent",
+                    age_seconds = time_since_last_fetch.num_seconds()
+                );
+                return false;
             }
         }
 
-        let gh_client = GithubClient::new().await?;
-        let prs = self.fetch_prs(&gh_client).await;
+        true
+    }
+
+    /// Refetches prs through the gh cli unless the last fetch was within the past five minutes.
+    pub async fn update_session_prs(&mut self) -> Result<(), GithubClientError> {
+        if self.prepare_update() {
+            let gh_client = GithubClient::new().await?;
+            self.merge_fetched_prs(&gh_client).await;
+        }
+
+        Ok(())
+    }
+
+    /// Like `update_session_prs`, but fetching through `fetcher` instead of the gh cli. Returns
+    /// whether a fetch ran, the last one may still be recent enough to reuse.
+    pub async fn update_session_prs_with<F: PrFetcher>(&mut self, fetcher: &F) -> bool {
+        let update = self.prepare_update();
+        if update {
+            self.merge_fetched_prs(fetcher).await;
+        }
+        update
+    }
+
+    /// Replaces the tracked prs with freshly fetched ones, resetting acknowledgement on new reviews.
+    /// Within an hour of a full fetch only prs updated since the last fetch are asked for.
+    async fn merge_fetched_prs<F: PrFetcher>(&mut self, fetcher: &F) {
+        let started = Instant::now();
+        let fetch_started = Utc::now();
+
+        if self.can_fetch_incrementally(fetch_started) {
+            if let Some(updates) = self.fetch_updated_repository_prs(fetcher).await {
+                logging::info!(
+                    "fetched updated session prs",
+                    repositories = self.repositories.len(),
+                    count = updates.iter().map(|(_, prs)| prs.len()).sum::<usize>(),
+                    duration_ms = started.elapsed().as_millis()
+                );
+                self.last_fetch_time = Some(Utc::now());
+                self.merge_updated_prs(updates, fetch_started);
+                return;
+            }
+            logging::debug!("falling back to a full fetch");
+        }
+
+        let repository_prs = self.fetch_repository_prs(fetcher).await;
+        let prs: Vec<GithubPRStatus> = repository_prs
+            .iter()
+            .flat_map(|(_, prs)| prs.iter().flatten().cloned())
+            .collect();
+        logging::info!(
+            "fetched session prs",
+            repositories = self.repositories.len(),
+            count = prs.len(),
+            duration_ms = started.elapsed().as_millis()
+        );
         self.last_fetch_time = Some(Utc::now());
+        self.last_full_fetch_time = Some(fetch_started);
+        for (repository, prs) in &repository_prs {
+            if let Some(prs) = prs {
+                let newest_update = prs.iter().filter_map(|pr| pr.updated_at).max();
+                self.advance_watermark(repository, newest_update, fetch_started);
+            }
+        }
 
         let mut still_existing_prs = HashSet::new();
 
         for pr in prs {
             still_existing_prs.insert(pr.id.clone());
-            match self.prs.get_mut(&pr.id) {
-                Some(session_pr) => {
-                    if let Some(incoming_latest_review_time) = pr.latest_review_time() {
-                        let session_pr_latest_review_time = session_pr.pr.latest_review_time();
-
-                        let incoming_has_new_review = session_pr_latest_review_time
-                            .map(|session_latest_review_time| {
-                                incoming_latest_review_time > session_latest_review_time
-                            })
-                            .unwrap_or(true);
-
-                        if incoming_has_new_review {
-                            session_pr.acknowledged = false;
+            self.upsert_pr(pr);
+        }
+
+        let vanished_pr_ids: Vec<PullRequestId> = self
+            .prs
+            .keys()
+            .filter(|id| !still_existing_prs.contains(*id))
+            .cloned()
+            .collect();
+        let vanished_prs: Vec<SessionPr> = vanished_pr_ids
+            .iter()
+            .filter_map(|id| self.prs.remove(id))
+            .collect();
+
+        self.archive_vanished_prs(fetcher, vanished_prs).await;
+    }
+
+    /// Applies prs updated since the last fetch, open ones are tracked and merged or closed
+    /// ones are archived.
+    fn merge_updated_prs(
+        &mut self,
+        updates: Vec<(String, Vec<(GithubPRStatus, GithubPRState)>)>,
+        fetch_started: DateTime<Utc>,
+    ) {
+        let now = Utc::now();
+        for (repository, prs) in updates {
+            let newest_update = prs.iter().filter_map(|(pr, _)| pr.updated_at).max();
+            self.advance_watermark(&repository, newest_update, fetch_started);
+
+            for (pr, state) in prs {
+                match state {
+                    GithubPRState::Open => self.upsert_pr(pr),
+                    state => {
+                        if let Some(mut session_pr) = self.prs.remove(&pr.id) {
+                            session_pr.pr = pr;
+                            self.archive_pr(session_pr, state, now);
                         }
                     }
-
-                    session_pr.pr = pr.clone();
-                }
-                None => {
-                    self.prs.insert(
-                        pr.id.clone(),
-                        SessionPr {
-                            acknowledged: false,
-                            pr: pr.clone(),
-                        },
-                    );
                 }
-            };
+            }
         }
+    }
+
+    /// Tracks `pr`, or refreshes it if already tracked and resets its acknowledgement when it
+    /// got a new review.
+    fn upsert_pr(&mut self, pr: GithubPRStatus) {
+        self.archived.remove(&pr.id);
+        let incoming_latest_review_time = self.latest_actionable_review_time(&pr);
+        let session_pr_latest_review_time = self
+            .prs
+            .get(&pr.id)
+            .and_then(|session_pr| self.latest_actionable_review_time(&session_pr.pr));
+
+        match self.prs.get_mut(&pr.id) {
+            Some(session_pr) => {
+                if let Some(incoming_latest_review_time) = incoming_latest_review_time {
+                    let incoming_has_new_review = session_pr_latest_review_time
+                        .map(|session_latest_review_time| {
+                            incoming_latest_review_time > session_latest_review_time
+                        })
+                        .unwrap_or(true);
 
-        let session_pr_ids: Vec<PullRequestId> = self.prs.keys().cloned().collect();
+                    if incoming_has_new_review {
+                        session_pr.acknowledged = false;
+                        session_pr.acknowledged_at = None;
+                        session_pr.snoozed_until = None;
+                        session_pr.note = None;
+                    }
+                }
 
-        for session_pr_id in session_pr_ids {
-            if !still_existing_prs.contains(&session_pr_id) {
-                self.prs.remove(&session_pr_id);
+                session_pr.pr = pr;
             }
-        }
+            None => {
+                self.prs.insert(
+                    pr.id.clone(),
+                    SessionPr {
+                        acknowledged: false,
+                        pr,
+                        snoozed_until: None,
+                        acknowledged_at: None,
+                        ignored: false,
+                        note: None,
+                    },
+                );
+            }
+        };
+    }
 
-        Ok(())
+    fn archive_pr(&mut self, session_pr: SessionPr, state: GithubPRState, now: DateTime<Utc>) {
+        logging::info!("archived pr", id = session_pr.pr.id, state = state);
+        self.archived.insert(
+            session_pr.pr.id.clone(),
+            ArchivedPr {
+                pr: session_pr.pr,
+                state,
+                archived_at: now,
+                acknowledged: session_pr.acknowledged,
+                note: session_pr.note,
+                reported: false,
+            },
+        );
+    }
+
+    /// Archives prs that left the open list because they were merged or closed. Prs whose state
+    /// can't be looked up stay tracked until the next fetch, ones still open are dropped since
+    /// they no longer match the configured repositories and authors.
+    async fn archive_vanished_prs<F: PrFetcher>(&mut self, fetcher: &F, prs: Vec<SessionPr>) {
+        use futures::future::join_all;
+
+        let states = join_all(prs.iter().map(|session_pr| async move {
+            match session_pr.pr.number {
+                Some(number) => Some(
+                    fetcher
+                        .fetch_pr_state(&session_pr.pr.repository, number)
+                        .await,
+                ),
+                None => None,
+            }
+        }))
+        .await;
+
+        let now = Utc::now();
+        for (session_pr, state) in prs.into_iter().zip(states) {
+            match state {
+                Some(Ok(GithubPRState::Open)) | None => {
+                    logging::debug!("dropped untracked pr", id = session_pr.pr.id);
+                }
+                Some(Ok(state)) => self.archive_pr(session_pr, state, now),
+                Some(Err(e)) => {
+                    logging::warn!(
+                        "failed to look up state of pr",
+                        id = session_pr.pr.id,
+                        error = e
+                    );
+                    self.prs.insert(session_pr.pr.id.clone(), session_pr);
+                }
+            }
+        }
     }
 }
 
@@ -191,17 +808,97 @@ pub async fn unacknowledged_prs(
 ) -> Result<Vec<GithubPRStatus>, GithubClientError> {
     session.update_session_prs().await?;
 
-    let prs = session
+    Ok(cached_unacknowledged_prs(session))
+}
+
+/// Lists unacknowledged prs from the session as it was last fetched, without contacting github.
+pub fn cached_unacknowledged_prs(session: &Session) -> Vec<GithubPRStatus> {
+    session
         .prs
-        .iter()
-        .filter_map(|(_, pr)| -> Option<GithubPRStatus> {
-            if !pr.acknowledged && !pr.pr.reviews.is_empty() {
+        .values()
+        .filter(|pr| !session.is_repository_muted(&pr.pr.repository))
+        .filter_map(|pr| -> Option<GithubPRStatus> {
+            if !pr.acknowledged
+                && !pr.ignored
+                && session.latest_actionable_review_time(&pr.pr).is_some()
+                && !pr.is_snoozed()
+            {
                 Some(pr.into())
             } else {
                 None
             }
         })
-        .collect::<Vec<GithubPRStatus>>();
+        .collect::<Vec<GithubPRStatus>>()
+}
+
+/// Narrows pr listings down by label and base branch.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct PrFilter {
+    /// Keep only prs with at least one of these labels, every pr if empty.
+    pub labels: Vec<String>,
+    /// Drop prs with any of these labels.
+    pub exclude_labels: Vec<String>,
+    /// Keep only prs targeting this branch.
+    pub base: Option<String>,
+}
+
+impl PrFilter {
+    /// Falls back to the session's `base_branch` when no base was asked for.
+    pub fn or_session_defaults(mut self, session: &Session) -> PrFilter {
+        if self.base.is_none() {
+            self.base = session.base_branch.clone();
+        }
+        self
+    }
+
+    pub fn matches(&self, pr: &GithubPRStatus) -> bool {
+        let included = self.labels.is_empty() || self.labels.iter().any(|l| pr.has_label(l));
+        let excluded = self.exclude_labels.iter().any(|l| pr.has_label(l));
+        let on_base = self
+            .base
+            .as_ref()
+            .is_none_or(|base| pr.base_branch.as_deref() == Some(base.as_str()));
+
+        included && !excluded && on_base
+    }
+
+    pub fn apply(&self, prs: Vec<GithubPRStatus>) -> Vec<GithubPRStatus> {
+        prs.into_iter().filter(|pr| self.matches(pr)).collect()
+    }
+}
+
+/// Prs with their required approvals, acknowledged or not, that only need merging.
+pub async fn ready_to_merge_prs(
+    session: &mut Session,
+) -> Result<Vec<GithubPRStatus>, GithubClientError> {
+    session.update_session_prs().await?;
+
+    Ok(session
+        .prs
+        .values()
+        .filter(|pr| !session.is_repository_muted(&pr.pr.repository))
+        .filter(|pr| !pr.ignored && pr.pr.is_ready_to_merge())
+        .map(GithubPRStatus::from)
+        .collect())
+}
+
+/// Unacknowledged prs whose newest review is older than `older_than`, oldest first.
+pub async fn stale_prs(
+    session: &mut Session,
+    older_than: Duration,
+) -> Result<Vec<GithubPRStatus>, GithubClientError> {
+    let now = Utc::now();
+    let mut prs: Vec<GithubPRStatus> = unacknowledged_prs(session)
+        .await?
+        .into_iter()
+        .filter(|pr| {
+            session
+                .latest_actionable_review_time(pr)
+                .is_some_and(|time| now.signed_duration_since(time) > older_than)
+        })
+        .collect();
+    prs.sort_by_key(|pr| session.latest_actionable_review_time(pr));
 
     Ok(prs)
 }
@@ -209,30 +906,199 @@ pub async fn unacknowledged_prs(
 pub async fn acknowledge_review(
     session: &mut Session,
     pr_id: &PullRequestId,
-) -> anyhow::Result<()> {
+    note: Option<String>,
+) -> Result<(), SessionError> {
     session.update_session_prs().await?;
 
     match session.prs.get_mut(pr_id) {
         Some(pr) => {
+            let previous = pr.clone();
             pr.acknowledged = true;
+            pr.acknowledged_at = Some(Utc::now());
+            pr.note = note;
+            session.record_operation(pr_id, AcknowledgementOperation::Acknowledge, previous);
             Ok(())
         }
-        None => Err(anyhow!("Could not find PR with ID: {pr_id}")),
+        None => Err(SessionError::UnknownPr(pr_id.clone())),
     }
 }
 
+/// What acknowledging or unacknowledging a pr would change, see `preview_acknowledgement`.
+#[derive(Clone, Debug, Serialize)]
+pub struct AcknowledgementPreview {
+    pub pr: GithubPRStatus,
+    /// Whether the pr is acknowledged now.
+    pub acknowledged: bool,
+    /// The actionable reviews, which acknowledging hides and unacknowledging lists again.
+    pub reviews: Vec<GithubPRReview>,
+}
+
+/// Looks up what acknowledging or unacknowledging `pr_id` would change without changing it.
+pub async fn preview_acknowledgement(
+    session: &mut Session,
+    pr_id: &PullRequestId,
+) -> Result<AcknowledgementPreview, SessionError> {
+    session.update_session_prs().await?;
+
+    let session_pr = session
+        .prs
+        .get(pr_id)
+        .ok_or_else(|| SessionError::UnknownPr(pr_id.clone()))?;
+    Ok(AcknowledgementPreview {
+        pr: session_pr.pr.clone(),
+        acknowledged: session_pr.acknowledged,
+        reviews: session
+            .actionable_reviews(&session_pr.pr)
+            .cloned()
+            .collect(),
+    })
+}
+
 pub async fn unacknowledge_review(
     session: &mut Session,
     pr_id: &PullRequestId,
-) -> anyhow::Result<()> {
+) -> Result<(), SessionError> {
     session.update_session_prs().await?;
 
     match session.prs.get_mut(pr_id) {
         Some(pr) => {
+            let previous = pr.clone();
             pr.acknowledged = false;
+            pr.acknowledged_at = None;
+            pr.note = None;
+            session.record_operation(pr_id, AcknowledgementOperation::Unacknowledge, previous);
             Ok(())
         }
-        None => Err(anyhow!("Could not find PR with ID: {pr_id}")),
+        None => Err(SessionError::UnknownPr(pr_id.clone())),
+    }
+}
+
+pub async fn snooze_review(
+    session: &mut Session,
+    pr_id: &PullRequestId,
+    snoozed_until: DateTime<Utc>,
+) -> Result<(), SessionError> {
+    session.update_session_prs().await?;
+
+    match session.prs.get_mut(pr_id) {
+        Some(pr) => {
+            pr.snoozed_until = Some(snoozed_until);
+            Ok(())
+        }
+        None => Err(SessionError::UnknownPr(pr_id.clone())),
+    }
+}
+
+/// Wakes a snoozed pr so it shows up in the unacknowledged listing again.
+pub async fn unsnooze_review(
+    session: &mut Session,
+    pr_id: &PullRequestId,
+) -> Result<(), SessionError> {
+    session.update_session_prs().await?;
+
+    match session.prs.get_mut(pr_id) {
+        Some(pr) => {
+            pr.snoozed_until = None;
+            Ok(())
+        }
+        None => Err(SessionError::UnknownPr(pr_id.clone())),
+    }
+}
+
+/// Prs that are currently snoozed along with when they wake, soonest first.
+pub async fn snoozed_prs(
+    session: &mut Session,
+) -> Result<Vec<(GithubPRStatus, DateTime<Utc>)>, GithubClientError> {
+    session.update_session_prs().await?;
+
+    let mut prs: Vec<(GithubPRStatus, DateTime<Utc>)> = session
+        .prs
+        .values()
+        .filter(|pr| pr.is_snoozed())
+        .filter_map(|pr| Some((pr.pr.clone(), pr.snoozed_until?)))
+        .collect();
+    prs.sort_by_key(|(_, snoozed_until)| *snoozed_until);
+
+    Ok(prs)
+}
+
+pub async fn set_ignored(
+    session: &mut Session,
+    pr_id: &PullRequestId,
+    ignored: bool,
+) -> Result<(), SessionError> {
+    session.update_session_prs().await?;
+
+    match session.prs.get_mut(pr_id) {
+        Some(pr) => {
+            pr.ignored = ignored;
+            Ok(())
+        }
+        None => Err(SessionError::UnknownPr(pr_id.clone())),
+    }
+}
+
+pub async fn ignored_prs(session: &mut Session) -> Result<Vec<GithubPRStatus>, GithubClientError> {
+    session.update_session_prs().await?;
+
+    Ok(session
+        .prs
+        .values()
+        .filter(|pr| pr.ignored)
+        .map(GithubPRStatus::from)
+        .collect())
+}
+
+/// Merged and closed prs within the archive retention, most recently archived first.
+pub async fn archived_prs(session: &mut Session) -> Result<Vec<ArchivedPr>, GithubClientError> {
+    session.update_session_prs().await?;
+
+    let mut prs: Vec<ArchivedPr> = session.archived.values().cloned().collect();
+    prs.sort_by(|a, b| {
+        b.archived_at
+            .cmp(&a.archived_at)
+            .then_with(|| a.pr.id.cmp(&b.pr.id))
+    });
+
+    Ok(prs)
+}
+
+/// Archived prs that haven't been reported yet, marking them as reported so each merge or close
+/// is only summarised once.
+pub fn take_unreported_archived_prs(session: &mut Session) -> Vec<ArchivedPr> {
+    session
+        .archived
+        .values_mut()
+        .filter(|archived| !archived.reported)
+        .map(|archived| {
+            archived.reported = true;
+            archived.clone()
+        })
+        .collect()
+}
+
+pub async fn mute_repository(
+    session: &mut Session,
+    repository: &str,
+    muted_until: DateTime<Utc>,
+) -> Result<(), SessionError> {
+    if !session.repositories.contains(repository) {
+        return Err(SessionError::UnknownRepository(repository.to_string()));
+    }
+
+    session
+        .muted_repositories
+        .insert(repository.to_string(), muted_until);
+    Ok(())
+}
+
+pub async fn unmute_repository(
+    session: &mut Session,
+    repository: &str,
+) -> Result<(), SessionError> {
+    match session.muted_repositories.remove(repository) {
+        Some(_) => Ok(()),
+        None => Err(SessionError::NotMuted(repository.to_string())),
     }
 }
 
@@ -243,9 +1109,10 @@ pub async fn acknowledged_prs(
 
     Ok(session
         .prs
-        .iter()
-        .filter_map(|(_, pr)| -> Option<GithubPRStatus> {
-            if pr.acknowledged {
+        .values()
+        .filter(|pr| !session.is_repository_muted(&pr.pr.repository))
+        .filter_map(|pr| -> Option<GithubPRStatus> {
+            if pr.acknowledged && !pr.ignored {
                 Some(pr.into())
             } else {
                 None
@@ -254,6 +1121,314 @@ pub async fn acknowledged_prs(
         .collect::<Vec<GithubPRStatus>>())
 }
 
+/// Puts the pr of the last journalled operation back the way it was and drops the operation
+/// from the history, so an undone ack doesn't count towards stats.
+pub async fn undo_last_operation(session: &mut Session) -> Result<JournalEntry, SessionError> {
+    let Some(entry) = session.journal.pop() else {
+        return Err(SessionError::NothingToUndo);
+    };
+
+    let Some(pr) = session.prs.get_mut(&entry.pr_id) else {
+        return Err(SessionError::NoLongerTracked(entry.pr_id));
+    };
+    pr.acknowledged = entry.previously_acknowledged;
+    // Entries journalled before the ack time was kept have none to restore
+    pr.acknowledged_at = entry
+        .previously_acknowledged_at
+        .or_else(|| entry.previously_acknowledged.then(Utc::now));
+    pr.note = entry.previous_note.clone();
+
+    if let Some(position) = session
+        .history
+        .iter()
+        .rposition(|history| history.pr.id == entry.pr_id && history.operation == entry.operation)
+    {
+        session.history.remove(position);
+    }
+
+    Ok(entry)
+}
+
 pub async fn clear_session(session: &mut Session) {
     session.prs.clear();
+    session.journal.clear();
+    session.fetch_watermarks.clear();
+    session.last_full_fetch_time = None;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gh_client::Result;
+    use serde_json::json;
+    use std::sync::Mutex;
+
+    fn pr(id: &str, repository: &str, reviews: Vec<GithubPRReview>) -> GithubPRStatus {
+        serde_json::from_value(json!({
+            "id": id,
+            "reviews": reviews,
+            "title": format!("pr {id}"),
+            "repository": repository,
+        }))
+        .unwrap()
+    }
+
+    /// A session tracking `prs` that was just fetched, so operations don't call gh.
+    fn session(prs: Vec<GithubPRStatus>) -> Session {
+        let config = SessionConfig {
+            author: "@me".to_string(),
+            repositories: prs.iter().map(|pr| pr.repository.clone()).collect(),
+            repository_authors: HashMap::new(),
+            ack_ttl: None,
+            stale_after: None,
+            required_reviewers: None,
+            base_branch: None,
+            archive_retention: None,
+        };
+        let mut session = Session::new(config, SessionState::default());
+        for pr in prs {
+            session.prs.insert(
+                pr.id.clone(),
+                SessionPr {
+                    acknowledged: false,
+                    pr,
+                    snoozed_until: None,
+                    acknowledged_at: None,
+                    ignored: false,
+                    note: None,
+                },
+            );
+        }
+        session.last_fetch_time = Some(Utc::now());
+        session
+    }
+
+    fn review(id: &str, submitted_at: DateTime<Utc>) -> GithubPRReview {
+        serde_json::from_value(json!({
+            "id": id,
+            "author": { "login": "alice" },
+            "submittedAt": submitted_at,
+        }))
+        .unwrap()
+    }
+
+    fn numbered(mut pr: GithubPRStatus, number: u64, updated_at: DateTime<Utc>) -> GithubPRStatus {
+        pr.number = Some(number);
+        pr.updated_at = Some(updated_at);
+        pr
+    }
+
+    /// Serves canned prs and records which kind of fetch was made.
+    #[derive(Default)]
+    struct FakeFetcher {
+        open: HashMap<String, Vec<GithubPRStatus>>,
+        states: HashMap<u64, GithubPRState>,
+        updated: Option<Vec<(GithubPRStatus, GithubPRState)>>,
+        calls: Mutex<Vec<String>>,
+    }
+
+    impl PrFetcher for FakeFetcher {
+        async fn fetch_prs(&self, repository: &str, _author: &str) -> Result<Vec<GithubPRStatus>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("full {repository}"));
+            Ok(self.open.get(repository).cloned().unwrap_or_default())
+        }
+
+        async fn fetch_pr_state(&self, _repository: &str, number: u64) -> Result<GithubPRState> {
+            Ok(self
+                .states
+                .get(&number)
+                .copied()
+                .unwrap_or(GithubPRState::Open))
+        }
+
+        async fn fetch_updated_prs(
+            &self,
+            repository: &str,
+            _author: &str,
+            since: DateTime<Utc>,
+        ) -> Result<Option<Vec<(GithubPRStatus, GithubPRState)>>> {
+            self.calls
+                .lock()
+                .unwrap()
+                .push(format!("updated {repository} {}", since.to_rfc3339()));
+            Ok(self.updated.clone())
+        }
+    }
+
+    /// A session whose last full fetch left `watermark` on every repository, due for a refresh.
+    fn incremental_session(prs: Vec<GithubPRStatus>, watermark: DateTime<Utc>) -> Session {
+        let mut session = session(prs);
+        for repository in session.repositories.clone() {
+            session.fetch_watermarks.insert(
+                repository,
+                FetchWatermark {
+                    author: "@me".to_string(),
+                    updated_at: watermark,
+                },
+            );
+        }
+        session.last_fetch_time = None;
+        session.last_full_fetch_time = Some(Utc::now() - Duration::minutes(10));
+        session
+    }
+
+    fn acknowledgements(session: &Session) -> usize {
+        session
+            .history
+            .iter()
+            .filter(|entry| entry.operation == AcknowledgementOperation::Acknowledge)
+            .count()
+    }
+
+    #[test]
+    fn undoing_an_ack_drops_it_from_the_history() {
+        let mut session = session(vec![pr("a", "org/a", Vec::new())]);
+        let id = "a".to_string();
+
+        smol::block_on(async {
+            acknowledge_review(&mut session, &id, None).await.unwrap();
+            undo_last_operation(&mut session).await.unwrap();
+        });
+
+        assert!(!session.prs[&id].acknowledged);
+        assert_eq!(session.prs[&id].acknowledged_at, None);
+        assert_eq!(acknowledgements(&session), 0);
+        assert!(session.journal.is_empty());
+    }
+
+    #[test]
+    fn undoing_an_unack_restores_the_ack_time_and_note() {
+        let mut session = session(vec![pr("a", "org/a", Vec::new())]);
+        let id = "a".to_string();
+        let acknowledged_at = Utc::now() - Duration::days(3);
+
+        smol::block_on(async {
+            acknowledge_review(&mut session, &id, Some("waiting on ci".to_string()))
+                .await
+                .unwrap();
+            session.prs.get_mut(&id).unwrap().acknowledged_at = Some(acknowledged_at);
+            unacknowledge_review(&mut session, &id).await.unwrap();
+            undo_last_operation(&mut session).await.unwrap();
+        });
+
+        let session_pr = &session.prs[&id];
+        assert!(session_pr.acknowledged);
+        assert_eq!(session_pr.acknowledged_at, Some(acknowledged_at));
+        assert_eq!(session_pr.note.as_deref(), Some("waiting on ci"));
+        assert_eq!(acknowledgements(&session), 1);
+        assert_eq!(session.history.len(), 1);
+    }
+
+    #[test]
+    fn full_fetch_archives_merged_prs_and_sets_watermarks() {
+        let updated_at = Utc::now() - Duration::days(1);
+        let open = numbered(pr("a", "org/a", Vec::new()), 1, updated_at);
+        let merged = numbered(pr("b", "org/a", Vec::new()), 2, updated_at);
+        let mut session = session(vec![open.clone(), merged]);
+        session.last_fetch_time = None;
+        let fetcher = FakeFetcher {
+            open: HashMap::from([("org/a".to_string(), vec![open])]),
+            states: HashMap::from([(2, GithubPRState::Merged)]),
+            ..FakeFetcher::default()
+        };
+
+        assert!(smol::block_on(session.update_session_prs_with(&fetcher)));
+
+        assert!(session.prs.contains_key("a"));
+        assert!(!session.prs.contains_key("b"));
+        assert_eq!(session.archived["b"].state, GithubPRState::Merged);
+        assert!(session.last_full_fetch_time.is_some());
+        let watermark = &session.fetch_watermarks["org/a"];
+        assert_eq!(watermark.author, "@me");
+        assert!(watermark.updated_at > updated_at);
+    }
+
+    #[test]
+    fn recent_fetch_is_reused() {
+        let mut session = session(vec![pr("a", "org/a", Vec::new())]);
+        let fetcher = FakeFetcher::default();
+
+        assert!(!smol::block_on(session.update_session_prs_with(&fetcher)));
+
+        assert!(fetcher.calls.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn incremental_fetch_applies_updates_since_the_watermark() {
+        let watermark = Utc::now() - Duration::minutes(30);
+        let reviewed = numbered(pr("a", "org/a", Vec::new()), 1, watermark);
+        let closed = numbered(pr("b", "org/a", Vec::new()), 2, watermark);
+        let mut session = incremental_session(vec![reviewed.clone(), closed.clone()], watermark);
+        session.prs.get_mut("a").unwrap().acknowledged = true;
+
+        let newest_update = Utc::now() + Duration::hours(1);
+        let mut reviewed = numbered(reviewed, 1, newest_update);
+        reviewed.reviews.push(review("new", Utc::now()));
+        let fetcher = FakeFetcher {
+            updated: Some(vec![
+                (reviewed, GithubPRState::Open),
+                (closed, GithubPRState::Closed),
+            ]),
+            ..FakeFetcher::default()
+        };
+
+        assert!(smol::block_on(session.update_session_prs_with(&fetcher)));
+
+        assert_eq!(
+            *fetcher.calls.lock().unwrap(),
+            vec![format!("updated org/a {}", watermark.to_rfc3339())]
+        );
+        assert!(!session.prs["a"].acknowledged);
+        assert_eq!(session.prs["a"].pr.reviews.len(), 1);
+        assert!(!session.prs.contains_key("b"));
+        assert_eq!(session.archived["b"].state, GithubPRState::Closed);
+        assert_eq!(session.fetch_watermarks["org/a"].updated_at, newest_update);
+    }
+
+    #[test]
+    fn incremental_fetch_falls_back_to_a_full_fetch_when_unsupported() {
+        let watermark = Utc::now() - Duration::minutes(30);
+        let mut session = incremental_session(vec![pr("a", "org/a", Vec::new())], watermark);
+        let fetcher = FakeFetcher::default();
+
+        assert!(smol::block_on(session.update_session_prs_with(&fetcher)));
+
+        let calls = fetcher.calls.lock().unwrap();
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[1], "full org/a");
+        assert!(session.last_full_fetch_time.unwrap() > watermark);
+    }
+
+    #[test]
+    fn watermark_of_another_author_forces_a_full_fetch() {
+        let watermark = Utc::now() - Duration::minutes(30);
+        let mut session = incremental_session(vec![pr("a", "org/a", Vec::new())], watermark);
+        session
+            .repository_authors
+            .insert("org/a".to_string(), "bob".to_string());
+        let fetcher = FakeFetcher {
+            updated: Some(Vec::new()),
+            ..FakeFetcher::default()
+        };
+
+        assert!(smol::block_on(session.update_session_prs_with(&fetcher)));
+
+        assert_eq!(
+            *fetcher.calls.lock().unwrap(),
+            vec!["full org/a".to_string()]
+        );
+        assert_eq!(session.fetch_watermarks["org/a"].author, "bob");
+    }
+
+    #[test]
+    fn undo_without_operations_fails() {
+        let mut session = session(Vec::new());
+
+        let result = smol::block_on(undo_last_operation(&mut session));
+
+        assert!(matches!(result, Err(SessionError::NothingToUndo)));
+    }
 }
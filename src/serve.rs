@@ -0,0 +1,630 @@
+use std::{io::Write, time::Duration};
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use smol::{
+    io::{AsyncBufReadExt, BufReader},
+    stream::StreamExt,
+    Timer, Unblock,
+};
+
+use crate::{
+    duration::{from_now, parse_duration, parse_since},
+    gh_client::{
+        GithubClient, GithubPRReview, GithubPRState, GithubPRStatus, RateLimit, ReviewCounts,
+    },
+    lock_session, logging,
+    prs::{
+        acknowledge_review, acknowledged_prs, preview_acknowledgement, snooze_review, snoozed_prs,
+        stale_prs, unacknowledge_review, unacknowledged_prs, unsnooze_review,
+        AcknowledgementPreview, PrFilter, PullRequestId, Session, FETCH_INTERVAL_MINUTES,
+    },
+    reviewed_since, sort_prs, sort_prs_by,
+    stats::session_stats,
+    Args, SortKey,
+};
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SESSION_ERROR: i64 = -32000;
+
+const DEFAULT_SUBSCRIBE_INTERVAL_SECONDS: u64 = 60;
+
+#[derive(Deserialize)]
+struct Request {
+    id: Option<Value>,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Deserialize, Default)]
+struct ListParams {
+    #[serde(default)]
+    acknowledged: bool,
+    #[serde(flatten)]
+    filter: PrFilter,
+    /// Only prs in this repository.
+    repository: Option<String>,
+    /// Only prs reviewed within this window, a duration such as `2d` or a date such as
+    /// `2024-01-01`.
+    since: Option<String>,
+    /// Overrides the `--sort` ghp was started with.
+    sort: Option<SortKey>,
+    #[serde(default)]
+    reverse: bool,
+}
+
+#[derive(Deserialize)]
+struct StaleParams {
+    older_than: Option<String>,
+    #[serde(flatten)]
+    filter: PrFilter,
+}
+
+#[derive(Deserialize)]
+struct AckParams {
+    id: String,
+    note: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Deserialize)]
+struct AckManyParams {
+    #[serde(default)]
+    ids: Vec<String>,
+    /// Acknowledge every unacknowledged pr instead of `ids`.
+    #[serde(default)]
+    all: bool,
+    note: Option<String>,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct RpcAckResult {
+    id: String,
+    /// Why the pr couldn't be acknowledged, absent on success.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// The reviews that would be hidden, only set for dry runs.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reviews: Option<Vec<GithubPRReview>>,
+}
+
+#[derive(Deserialize)]
+struct UnackParams {
+    id: String,
+    #[serde(default)]
+    dry_run: bool,
+}
+
+#[derive(Serialize)]
+struct RpcAcknowledgementPreview {
+    id: String,
+    /// Whether the pr is acknowledged now.
+    acknowledged: bool,
+    reviews: Vec<GithubPRReview>,
+}
+
+impl From<AcknowledgementPreview> for RpcAcknowledgementPreview {
+    fn from(value: AcknowledgementPreview) -> Self {
+        RpcAcknowledgementPreview {
+            id: value.pr.id,
+            acknowledged: value.acknowledged,
+            reviews: value.reviews,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct SnoozeParams {
+    id: String,
+    duration: String,
+}
+
+#[derive(Deserialize)]
+struct UnsnoozeParams {
+    id: String,
+}
+
+#[derive(Serialize)]
+struct RpcSnoozedPr {
+    #[serde(flatten)]
+    pr: RpcPr,
+    snoozed_until: DateTime<Utc>,
+}
+
+#[derive(Serialize)]
+struct RpcRateLimit {
+    #[serde(flatten)]
+    rate_limit: RateLimit,
+    /// How long a fetch is reused before github is contacted again.
+    fetch_interval_seconds: i64,
+    subscribe_interval_seconds: Option<u64>,
+}
+
+#[derive(Deserialize)]
+struct SubscribeParams {
+    interval_seconds: Option<u64>,
+}
+
+#[derive(Serialize, PartialEq)]
+struct RpcPr {
+    id: String,
+    title: String,
+    repository: String,
+    url: Option<String>,
+    number: Option<u64>,
+    latest_review_time: Option<DateTime<Utc>>,
+    reviewers: Vec<String>,
+    review_counts: ReviewCounts,
+    conflicts: bool,
+    labels: Vec<String>,
+    base_branch: Option<String>,
+    note: Option<String>,
+}
+
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl ToString) -> RpcError {
+        RpcError {
+            code,
+            message: message.to_string(),
+        }
+    }
+}
+
+fn rpc_prs(session: &Session, prs: &[GithubPRStatus]) -> Vec<RpcPr> {
+    prs.iter()
+        .map(|pr| RpcPr {
+            id: pr.id.clone(),
+            title: pr.title.clone(),
+            repository: pr.repository.clone(),
+            url: pr.url.clone(),
+            number: pr.number,
+            latest_review_time: pr.latest_review_time(),
+            reviewers: pr.reviewers().into_iter().map(str::to_string).collect(),
+            review_counts: pr.review_counts(),
+            conflicts: pr.has_conflicts(),
+            labels: pr.labels.clone(),
+            base_branch: pr.base_branch.clone(),
+            note: session.acknowledgement_note(&pr.id).map(str::to_string),
+        })
+        .collect()
+}
+
+fn send(message: Value) {
+    let mut stdout = std::io::stdout().lock();
+    let _ = writeln!(stdout, "{message}");
+    let _ = stdout.flush();
+}
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, RpcError> {
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|e| RpcError::new(INVALID_PARAMS, e))
+}
+
+/// Locks, loads and saves the session around `operation` so other invocations can run between requests.
+async fn with_session<T>(
+    args: &Args,
+    operation: impl AsyncFnOnce(&mut Session) -> anyhow::Result<T>,
+) -> Result<T, RpcError> {
+    with_session_saving(args, true, operation).await
+}
+
+async fn with_session_saving<T>(
+    args: &Args,
+    save: bool,
+    operation: impl AsyncFnOnce(&mut Session) -> anyhow::Result<T>,
+) -> Result<T, RpcError> {
+    let session_error = |e: anyhow::Error| RpcError::new(SESSION_ERROR, e);
+
+    let mut session = lock_session(args, false).await.map_err(session_error)?;
+    let result = operation(&mut session).await;
+    if save {
+        session.save(args).map_err(session_error)?;
+    }
+
+    result.map_err(session_error)
+}
+
+/// Answers a dry run of acknowledging or unacknowledging `id`, leaving the session untouched.
+async fn preview(args: &Args, id: &PullRequestId) -> Result<Value, RpcError> {
+    let preview = with_session_saving(args, false, async |session| {
+        Ok(preview_acknowledgement(session, id).await?)
+    })
+    .await?;
+    Ok(json!(RpcAcknowledgementPreview::from(preview)))
+}
+
+async fn list(args: &Args, params: &ListParams) -> Result<Vec<RpcPr>, RpcError> {
+    let reviewed_after = params
+        .since
+        .as_deref()
+        .map(parse_since)
+        .transpose()
+        .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+
+    with_session(args, async |session| {
+        let prs = if params.acknowledged {
+            acknowledged_prs(session).await?
+        } else {
+            unacknowledged_prs(session).await?
+        };
+        let filter = params.filter.clone().or_session_defaults(session);
+        let prs: Vec<GithubPRStatus> = filter
+            .apply(prs)
+            .into_iter()
+            .filter(|pr| {
+                params
+                    .repository
+                    .as_ref()
+                    .is_none_or(|repository| pr.repository == *repository)
+            })
+            .filter(|pr| reviewed_since(session, pr, reviewed_after))
+            .collect();
+
+        let prs = match params.sort {
+            Some(sort) => sort_prs_by(prs, sort, params.reverse),
+            None => sort_prs(prs, args),
+        };
+        Ok(rpc_prs(session, &prs))
+    })
+    .await
+}
+
+#[derive(Serialize)]
+struct RpcArchivedPr {
+    id: String,
+    title: String,
+    repository: String,
+    url: Option<String>,
+    state: GithubPRState,
+    archived_at: DateTime<Utc>,
+}
+
+struct Subscription {
+    timer: Timer,
+    interval_seconds: u64,
+    last_sent: Option<Vec<RpcPr>>,
+    archived_since: DateTime<Utc>,
+}
+
+impl Subscription {
+    /// Sends a `prs_changed` notification if the unacknowledged prs differ from the last ones sent,
+    /// and a `prs_archived` notification for prs merged or closed since the last refresh.
+    async fn notify_if_changed(&mut self, args: &Args) {
+        match list(args, &ListParams::default()).await {
+            Ok(prs) if self.last_sent.as_ref() != Some(&prs) => {
+                send(json!({
+                    "jsonrpc": "2.0",
+                    "method": "prs_changed",
+                    "params": { "prs": prs },
+                }));
+                self.last_sent = Some(prs);
+            }
+            Ok(_) => {}
+            Err(e) => logging::warn!("failed to refresh subscription", error = e.message),
+        }
+
+        let since = self.archived_since;
+        let archived = with_session(args, async |session| {
+            Ok(session
+                .archived
+                .values()
+                .filter(|archived| archived.archived_at > since)
+                .map(|archived| RpcArchivedPr {
+                    id: archived.pr.id.clone(),
+                    title: archived.pr.title.clone(),
+                    repository: archived.pr.repository.clone(),
+                    url: archived.pr.url.clone(),
+                    state: archived.state,
+                    archived_at: archived.archived_at,
+                })
+                .collect::<Vec<RpcArchivedPr>>())
+        })
+        .await;
+
+        match archived {
+            Ok(archived) => {
+                if let Some(latest) = archived.iter().map(|archived| archived.archived_at).max() {
+                    self.archived_since = latest;
+                    send(json!({
+                        "jsonrpc": "2.0",
+                        "method": "prs_archived",
+                        "params": { "prs": archived },
+                    }));
+                }
+            }
+            Err(e) => logging::warn!("failed to refresh subscription", error = e.message),
+        }
+    }
+}
+
+async fn handle(
+    args: &Args,
+    request: Request,
+    subscription: &mut Option<Subscription>,
+) -> Result<Value, RpcError> {
+    match request.method.as_str() {
+        "list" => {
+            let list_params: ListParams = params(request.params)?;
+            Ok(json!(list(args, &list_params).await?))
+        }
+        "stale" => {
+            let StaleParams { older_than, filter } = params(request.params)?;
+            let older_than = older_than
+                .map(|older_than| parse_duration(&older_than))
+                .transpose()
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+
+            let prs = with_session(args, async |session| {
+                let older_than = older_than.unwrap_or(session.stale_after());
+                let prs = stale_prs(session, older_than).await?;
+                let filter = filter.or_session_defaults(session);
+                Ok(rpc_prs(session, &filter.apply(prs)))
+            })
+            .await?;
+            Ok(json!(prs))
+        }
+        "ack" => {
+            let AckParams { id, note, dry_run } = params(request.params)?;
+            if dry_run {
+                return preview(args, &id).await;
+            }
+            with_session(args, async |session| {
+                Ok(acknowledge_review(session, &id, note).await?)
+            })
+            .await?;
+            Ok(Value::Null)
+        }
+        "ack_many" => {
+            let AckManyParams {
+                ids,
+                all,
+                note,
+                dry_run,
+            } = params(request.params)?;
+            if all != ids.is_empty() {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "pass either a non-empty ids array or all",
+                ));
+            }
+
+            let results = with_session_saving(args, !dry_run, async |session| {
+                let ids = if all {
+                    unacknowledged_prs(session)
+                        .await?
+                        .into_iter()
+                        .map(|pr| pr.id)
+                        .collect()
+                } else {
+                    ids
+                };
+
+                let mut results = Vec::new();
+                for id in ids {
+                    let result = if dry_run {
+                        preview_acknowledgement(session, &id)
+                            .await
+                            .map(|preview| Some(preview.reviews))
+                    } else {
+                        acknowledge_review(session, &id, note.clone())
+                            .await
+                            .map(|_| None)
+                    };
+                    let (reviews, error) = match result {
+                        Ok(reviews) => (reviews, None),
+                        Err(e) => (None, Some(e.to_string())),
+                    };
+                    results.push(RpcAckResult { id, error, reviews });
+                }
+                Ok(results)
+            })
+            .await?;
+            Ok(json!(results))
+        }
+        "unack" => {
+            let UnackParams { id, dry_run } = params(request.params)?;
+            if dry_run {
+                return preview(args, &id).await;
+            }
+            with_session(args, async |session| {
+                Ok(unacknowledge_review(session, &id).await?)
+            })
+            .await?;
+            Ok(Value::Null)
+        }
+        "snooze" => {
+            let SnoozeParams { id, duration } = params(request.params)?;
+            let snoozed_until = parse_duration(&duration)
+                .and_then(from_now)
+                .map_err(|e| RpcError::new(INVALID_PARAMS, e))?;
+            with_session(args, async |session| {
+                Ok(snooze_review(session, &id, snoozed_until).await?)
+            })
+            .await?;
+            Ok(Value::Null)
+        }
+        "unsnooze" => {
+            let UnsnoozeParams { id } = params(request.params)?;
+            with_session(args, async |session| {
+                Ok(unsnooze_review(session, &id).await?)
+            })
+            .await?;
+            Ok(Value::Null)
+        }
+        "snoozed" => {
+            let prs = with_session(args, async |session| {
+                let snoozed = snoozed_prs(session).await?;
+                let (prs, snoozed_until): (Vec<GithubPRStatus>, Vec<DateTime<Utc>>) =
+                    snoozed.into_iter().unzip();
+
+                Ok(rpc_prs(session, &prs)
+                    .into_iter()
+                    .zip(snoozed_until)
+                    .map(|(pr, snoozed_until)| RpcSnoozedPr { pr, snoozed_until })
+                    .collect::<Vec<RpcSnoozedPr>>())
+            })
+            .await?;
+            Ok(json!(prs))
+        }
+        "stats" => {
+            let stats = with_session(args, async |session| {
+                session.update_session_prs().await?;
+                Ok(session_stats(session))
+            })
+            .await?;
+            Ok(json!(stats))
+        }
+        "rate_limit" => {
+            if args.offline {
+                return Err(RpcError::new(
+                    SESSION_ERROR,
+                    "the github api quota is unknown while offline",
+                ));
+            }
+            let github_client = GithubClient::new()
+                .await
+                .map_err(|e| RpcError::new(SESSION_ERROR, e))?;
+            let rate_limit = github_client
+                .rate_limit()
+                .await
+                .map_err(|e| RpcError::new(SESSION_ERROR, e))?;
+
+            Ok(json!(RpcRateLimit {
+                rate_limit,
+                fetch_interval_seconds: FETCH_INTERVAL_MINUTES * 60,
+                subscribe_interval_seconds: subscription
+                    .as_ref()
+                    .map(|subscription| subscription.interval_seconds),
+            }))
+        }
+        "subscribe" => {
+            let SubscribeParams { interval_seconds } = params(request.params)?;
+            let interval = interval_seconds.unwrap_or(DEFAULT_SUBSCRIBE_INTERVAL_SECONDS);
+            if interval == 0 {
+                return Err(RpcError::new(
+                    INVALID_PARAMS,
+                    "interval_seconds must be positive",
+                ));
+            }
+            *subscription = Some(Subscription {
+                timer: Timer::interval(Duration::from_secs(interval)),
+                interval_seconds: interval,
+                last_sent: None,
+                archived_since: Utc::now(),
+            });
+            Ok(Value::Null)
+        }
+        "unsubscribe" => {
+            *subscription = None;
+            Ok(Value::Null)
+        }
+        method => Err(RpcError::new(
+            METHOD_NOT_FOUND,
+            format!("Unknown method '{method}'"),
+        )),
+    }
+}
+
+enum Event {
+    Line(Option<std::io::Result<String>>),
+    Tick,
+}
+
+/// Serves newline delimited JSON-RPC 2.0 on stdin/stdout until stdin closes.
+///
+/// Methods:
+///
+/// - `list` (`{"acknowledged": bool}`), narrowed by `repository` and by `since`, a duration or date the
+///   newest review must fall within, and ordered by `sort` (`latest_review`, `repo`, `title`,
+///   `age`, `size` or `files`) and `reverse`.
+/// - `stale` (`{"older_than": "3d"}`).
+/// - `ack` (`{"id", "note"}`).
+/// - `ack_many` (`{"ids": [...]}` or `{"all": true}`, with an optional `note`), answering with a
+///   per-id `error` where one failed.
+/// - `unack` (`{"id"}`).
+/// - `ack`, `ack_many` and `unack` take `"dry_run": true` to answer with the actionable `reviews`
+///   that would be hidden or listed again, without saving anything.
+/// - `snooze` (`{"id", "duration": "2h"}`), `unsnooze` (`{"id"}`) and `snoozed`, which lists
+///   snoozed prs with their `snoozed_until`, soonest first.
+/// - `stats`, the same figures as `ghp stats --json`.
+/// - `rate_limit`, the remaining github GraphQL quota alongside how often prs are refetched.
+/// - `subscribe` (`{"interval_seconds"}`) and `unsubscribe`.
+///
+/// `list` and `stale` also take `labels` and `exclude_labels` arrays and a `base` branch, which
+/// defaults to the `base_branch` config key. While subscribed, a `prs_changed` notification
+/// carrying the unacknowledged prs is sent whenever they change, and a `prs_archived`
+/// notification lists prs merged or closed since the previous one.
+pub async fn serve_stdio(args: &Args) -> anyhow::Result<()> {
+    let mut lines = BufReader::new(Unblock::new(std::io::stdin())).lines();
+    let mut subscription: Option<Subscription> = None;
+
+    loop {
+        let event = match &mut subscription {
+            Some(Subscription { timer, .. }) => {
+                smol::future::or(async { Event::Line(lines.next().await) }, async {
+                    timer.next().await;
+                    Event::Tick
+                })
+                .await
+            }
+            None => Event::Line(lines.next().await),
+        };
+
+        let line = match event {
+            Event::Tick => {
+                if let Some(subscription) = &mut subscription {
+                    subscription.notify_if_changed(args).await;
+                }
+                continue;
+            }
+            Event::Line(None) => return Ok(()),
+            Event::Line(Some(line)) => line?,
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let request: Request = match serde_json::from_str(&line) {
+            Ok(request) => request,
+            Err(e) => {
+                send(json!({
+                    "jsonrpc": "2.0",
+                    "id": null,
+                    "error": { "code": PARSE_ERROR, "message": e.to_string() },
+                }));
+                continue;
+            }
+        };
+
+        let id = request.id.clone();
+        let result = handle(args, request, &mut subscription).await;
+
+        // Requests without an id are notifications and get no response
+        if let Some(id) = id {
+            send(match result {
+                Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+                Err(RpcError { code, message }) => json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": code, "message": message },
+                }),
+            });
+        }
+
+        if let Some(subscription) = &mut subscription {
+            subscription.notify_if_changed(args).await;
+        }
+    }
+}
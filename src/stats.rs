@@ -0,0 +1,203 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::{Datelike, Duration, Utc};
+use serde::Serialize;
+use tabled::Tabled;
+
+use crate::{
+    duration::format_duration,
+    gh_client::{GithubPRReview, GithubPRStatus},
+    prs::{cached_unacknowledged_prs, AcknowledgementOperation, Session},
+};
+
+const TOP_REVIEWERS: usize = 5;
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+pub struct WeeklyReviews {
+    pub week: String,
+    pub reviews: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+pub struct ReviewerStats {
+    pub reviewer: String,
+    pub reviews: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Tabled)]
+pub struct RepositoryStats {
+    pub repository: String,
+    pub reviews: usize,
+    pub acknowledgements: usize,
+}
+
+/// Where a repository stands, one row of the summary command.
+#[derive(Serialize, Clone, Debug, Tabled)]
+pub struct RepositorySummary {
+    pub repository: String,
+    #[tabled(rename = "unacked")]
+    pub unacknowledged: usize,
+    #[tabled(rename = "acked")]
+    pub acknowledged: usize,
+    /// Age of the oldest review still waiting for an ack in this repository.
+    #[tabled(rename = "oldest unacked", display_with = "display_seconds")]
+    pub oldest_unacknowledged_seconds: Option<i64>,
+}
+
+fn display_seconds(seconds: &Option<i64>) -> String {
+    seconds
+        .map(|seconds| format_duration(Duration::seconds(seconds)))
+        .unwrap_or_default()
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SessionStats {
+    pub reviews_per_week: Vec<WeeklyReviews>,
+    pub median_seconds_to_acknowledge: Option<i64>,
+    pub top_reviewers: Vec<ReviewerStats>,
+    pub repositories: Vec<RepositoryStats>,
+    pub unacknowledged: usize,
+    /// Age of the oldest review still waiting for an ack.
+    pub oldest_unacknowledged_seconds: Option<i64>,
+    pub acknowledgements_last_week: usize,
+}
+
+/// Summarises every configured repository, and any other repository with prs, from its
+/// unacknowledged and acknowledged prs.
+pub fn repository_summaries(
+    session: &Session,
+    unacknowledged: &[GithubPRStatus],
+    acknowledged: &[GithubPRStatus],
+) -> Vec<RepositorySummary> {
+    let mut summaries: BTreeMap<&str, RepositorySummary> = BTreeMap::new();
+    let repository_summary = |repository: &str| RepositorySummary {
+        repository: repository.to_string(),
+        unacknowledged: 0,
+        acknowledged: 0,
+        oldest_unacknowledged_seconds: None,
+    };
+
+    for repository in &session.repositories {
+        summaries.insert(repository, repository_summary(repository));
+    }
+
+    let now = Utc::now();
+    for pr in unacknowledged {
+        let summary = summaries
+            .entry(&pr.repository)
+            .or_insert_with(|| repository_summary(&pr.repository));
+        summary.unacknowledged += 1;
+        if let Some(reviewed) = session.latest_actionable_review_time(pr) {
+            let waiting = now.signed_duration_since(reviewed).num_seconds();
+            summary.oldest_unacknowledged_seconds =
+                summary.oldest_unacknowledged_seconds.max(Some(waiting));
+        }
+    }
+    for pr in acknowledged {
+        summaries
+            .entry(&pr.repository)
+            .or_insert_with(|| repository_summary(&pr.repository))
+            .acknowledged += 1;
+    }
+
+    summaries.into_values().collect()
+}
+
+/// Computes review metrics from the tracked prs and the ack/unack history of a session.
+pub fn session_stats(session: &Session) -> SessionStats {
+    // Reviews are deduplicated by id since the same review shows up in every snapshot of a pr.
+    let mut reviews: HashMap<&str, (&str, &GithubPRReview)> = HashMap::new();
+    let snapshots = session
+        .prs
+        .values()
+        .map(|session_pr| session_pr.pr())
+        .chain(session.history.iter().map(|entry| &entry.pr));
+    for pr in snapshots {
+        for review in &pr.reviews {
+            reviews.insert(&review.id, (&pr.repository, review));
+        }
+    }
+
+    let mut reviews_per_week: BTreeMap<String, usize> = BTreeMap::new();
+    let mut reviewers: HashMap<&str, usize> = HashMap::new();
+    let mut repositories: BTreeMap<&str, RepositoryStats> = BTreeMap::new();
+
+    let repository_stats = |repository: &str| RepositoryStats {
+        repository: repository.to_string(),
+        reviews: 0,
+        acknowledgements: 0,
+    };
+
+    for (repository, review) in reviews.values() {
+        let week = review.submitted_at.iso_week();
+        *reviews_per_week
+            .entry(format!("{}-W{:02}", week.year(), week.week()))
+            .or_default() += 1;
+        *reviewers.entry(&review.author.login).or_default() += 1;
+        repositories
+            .entry(repository)
+            .or_insert_with(|| repository_stats(repository))
+            .reviews += 1;
+    }
+
+    let acknowledgements = session
+        .history
+        .iter()
+        .filter(|entry| entry.operation == AcknowledgementOperation::Acknowledge);
+
+    let now = Utc::now();
+    let mut acknowledgements_last_week = 0;
+    let mut seconds_to_acknowledge = Vec::new();
+    for entry in acknowledgements {
+        if now.signed_duration_since(entry.time) <= Duration::weeks(1) {
+            acknowledgements_last_week += 1;
+        }
+
+        repositories
+            .entry(&entry.pr.repository)
+            .or_insert_with(|| repository_stats(&entry.pr.repository))
+            .acknowledgements += 1;
+
+        if let Some(latest_review_time) = entry.pr.latest_review_time() {
+            seconds_to_acknowledge.push(
+                entry
+                    .time
+                    .signed_duration_since(latest_review_time)
+                    .num_seconds(),
+            );
+        }
+    }
+    seconds_to_acknowledge.sort_unstable();
+
+    let mut top_reviewers: Vec<ReviewerStats> = reviewers
+        .into_iter()
+        .map(|(reviewer, reviews)| ReviewerStats {
+            reviewer: reviewer.to_string(),
+            reviews,
+        })
+        .collect();
+    top_reviewers.sort_by(|a, b| b.reviews.cmp(&a.reviews).then(a.reviewer.cmp(&b.reviewer)));
+    top_reviewers.truncate(TOP_REVIEWERS);
+
+    let unacknowledged = cached_unacknowledged_prs(session);
+    let oldest_unacknowledged_seconds = unacknowledged
+        .iter()
+        .filter_map(|pr| session.latest_actionable_review_time(pr))
+        .min()
+        .map(|oldest| now.signed_duration_since(oldest).num_seconds());
+
+    SessionStats {
+        reviews_per_week: reviews_per_week
+            .into_iter()
+            .map(|(week, reviews)| WeeklyReviews { week, reviews })
+            .collect(),
+        median_seconds_to_acknowledge: seconds_to_acknowledge
+            .get(seconds_to_acknowledge.len() / 2)
+            .copied(),
+        top_reviewers,
+        repositories: repositories.into_values().collect(),
+        unacknowledged: unacknowledged.len(),
+        oldest_unacknowledged_seconds,
+        acknowledgements_last_week,
+    }
+}
@@ -0,0 +1,123 @@
+//! Helpers shared by the integration tests, not every test uses all of them.
+#![allow(dead_code)]
+
+use std::{
+    io::{Read, Write},
+    path::PathBuf,
+    process::{Command, Output, Stdio},
+};
+
+use serde_json::json;
+
+pub const PROMPT: &str = ">> Enter index or search: ";
+
+/// A config and fixture in a scratch directory, so invocations run offline against their own
+/// state file.
+pub struct Sandbox {
+    pub directory: PathBuf,
+}
+
+impl Sandbox {
+    pub fn new(name: &str) -> Sandbox {
+        let directory = std::env::temp_dir().join(format!("ghprs-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&directory);
+        std::fs::create_dir_all(&directory).unwrap();
+
+        let pr = |id: &str, repository: &str, reviewed_at: &str| {
+            json!({
+                "id": id,
+                "title": format!("pr {id}"),
+                "repository": repository,
+                "reviews": [{
+                    "id": format!("review {id}"),
+                    "author": { "login": "alice" },
+                    "submittedAt": reviewed_at,
+                    "state": "COMMENTED",
+                }],
+            })
+        };
+        let fixture = json!({
+            "recorded_at": "2026-10-15T00:00:00Z",
+            "repositories": {
+                "org/a": [pr("PR_a", "org/a", "2026-10-14T00:00:00Z")],
+                "org/b": [pr("PR_b", "org/b", "2026-10-13T00:00:00Z")],
+            },
+        });
+        std::fs::write(directory.join("fixture.json"), fixture.to_string()).unwrap();
+        std::fs::write(
+            directory.join("ghprs.toml"),
+            format!(
+                "author = \"me\"\nrepositories = [\"org/a\", \"org/b\"]\nsession_state_file = {:?}\nfixture_file = {:?}\n",
+                directory.join("state.json"),
+                directory.join("fixture.json"),
+            ),
+        )
+        .unwrap();
+
+        Sandbox { directory }
+    }
+
+    pub fn ghp(&self, args: &[&str]) -> Command {
+        let mut command = Command::new(env!("CARGO_BIN_EXE_ghp"));
+        command
+            .args(["--offline", "--no-pager"])
+            .args(args)
+            .env("GHPRS_CONFIG_FILE", self.directory.join("ghprs.toml"))
+            .env("XDG_CONFIG_HOME", &self.directory)
+            .env("XDG_STATE_HOME", &self.directory)
+            .env("NO_COLOR", "1")
+            // Fails any gh call instead of reaching github
+            .env("GHPRS_GH_REPLAY", self.directory.join("missing.jsonl"))
+            .env_remove("GHPRS_STATE_FILE")
+            .env_remove("GHPRS_PROFILE");
+        command
+    }
+
+    pub fn run(&self, args: &[&str], stdin: &str) -> Output {
+        let mut child = self
+            .ghp(args)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .unwrap();
+        child
+            .stdin
+            .take()
+            .unwrap()
+            .write_all(stdin.as_bytes())
+            .unwrap();
+        child.wait_with_output().unwrap()
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.directory);
+    }
+}
+
+/// Reads `reader` until it has printed `expected`.
+pub fn read_until(reader: &mut impl Read, expected: &str) -> String {
+    let mut output = Vec::new();
+    let mut byte = [0];
+    while !String::from_utf8_lossy(&output).ends_with(expected) {
+        match reader.read(&mut byte).unwrap() {
+            0 => panic!(
+                "output ended before {expected:?}: {}",
+                String::from_utf8_lossy(&output)
+            ),
+            _ => output.push(byte[0]),
+        }
+    }
+    String::from_utf8(output).unwrap()
+}
+
+pub fn assert_success(output: &Output) {
+    assert!(
+        output.status.success(),
+        "stdout: {}\nstderr: {}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+}
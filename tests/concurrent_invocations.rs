@@ -0,0 +1,61 @@
+use std::{
+    io::{Read, Write},
+    path::Path,
+    process::Stdio,
+    time::{Duration, Instant},
+};
+
+mod common;
+
+use common::{assert_success, read_until, Sandbox, PROMPT};
+
+#[test]
+fn invocations_run_while_another_waits_at_a_prompt() {
+    let sandbox = Sandbox::new("concurrent");
+    assert!(!Path::new(&sandbox.directory.join("state.json")).exists());
+
+    let mut prompting = sandbox
+        .ghp(&["ack"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let mut prompting_stdout = prompting.stdout.take().unwrap();
+    let listing = read_until(&mut prompting_stdout, PROMPT);
+    assert!(listing.contains("pr PR_a"));
+
+    let started = Instant::now();
+    let status = sandbox.run(&["status", "--tmux"], "");
+    assert_success(&status);
+    assert!(String::from_utf8_lossy(&status.stdout).contains("PR 2"));
+    let stats = sandbox.run(&["stats"], "");
+    assert_success(&stats);
+    assert!(String::from_utf8_lossy(&stats.stdout).contains("Unacknowledged: 2"));
+    assert!(started.elapsed() < Duration::from_secs(2));
+
+    let piped = sandbox.run(&["ack", "--stdin"], "PR_b\n");
+    assert_success(&piped);
+    assert!(started.elapsed() < Duration::from_secs(4));
+
+    // Index 0 is PR_a, the most recently reviewed pr in the listing shown before PR_b was acked
+    let mut stdin = prompting.stdin.take().unwrap();
+    stdin.write_all(b"0\n").unwrap();
+    drop(stdin);
+    let mut rest = String::new();
+    prompting_stdout.read_to_string(&mut rest).unwrap();
+    let prompted = prompting.wait_with_output().unwrap();
+    assert!(
+        prompted.status.success(),
+        "stderr: {}",
+        String::from_utf8_lossy(&prompted.stderr)
+    );
+    assert!(rest.contains("Selected 'pr PR_a'"), "{rest}");
+
+    // Neither ack overwrote the other
+    let acked = sandbox.run(&["fetch-acked", "--porcelain"], "");
+    assert_success(&acked);
+    let acked = String::from_utf8_lossy(&acked.stdout);
+    assert!(acked.contains("PR_a"), "{acked}");
+    assert!(acked.contains("PR_b"), "{acked}");
+}
@@ -0,0 +1,40 @@
+mod common;
+
+use common::{assert_success, Sandbox};
+
+/// Runs `args` interactively picking the first pr, returning the state file before and after.
+fn state_around(sandbox: &Sandbox, args: &[&str]) -> (Vec<u8>, Vec<u8>) {
+    let state_path = sandbox.directory.join("state.json");
+    let before = std::fs::read(&state_path).unwrap();
+
+    let output = sandbox.run(args, "0\n");
+    assert_success(&output);
+    assert!(
+        String::from_utf8_lossy(&output.stdout).contains("pr PR_a"),
+        "{}",
+        String::from_utf8_lossy(&output.stdout)
+    );
+
+    (before, std::fs::read(&state_path).unwrap())
+}
+
+#[test]
+fn interactive_ack_dry_run_leaves_the_state_untouched() {
+    let sandbox = Sandbox::new("dry-run-ack");
+    assert_success(&sandbox.run(&["fetch-acked"], ""));
+
+    // Forcing a refresh would otherwise move the fetch times in the saved state
+    let (before, after) = state_around(&sandbox, &["--force", "ack", "--dry-run"]);
+
+    assert_eq!(before, after);
+}
+
+#[test]
+fn interactive_unack_dry_run_leaves_the_state_untouched() {
+    let sandbox = Sandbox::new("dry-run-unack");
+    assert_success(&sandbox.run(&["ack", "--stdin"], "PR_a\n"));
+
+    let (before, after) = state_around(&sandbox, &["--force", "unack", "--dry-run"]);
+
+    assert_eq!(before, after);
+}
@@ -0,0 +1,94 @@
+mod common;
+
+use ghprs::migrations::STATE_VERSION;
+use serde_json::{json, Value};
+
+use common::{assert_success, Sandbox};
+
+/// State written before files were versioned.
+fn unversioned_state() -> String {
+    json!({
+        "last_fetch_time": null,
+        "prs": {},
+    })
+    .to_string()
+}
+
+#[test]
+fn older_state_is_backed_up_only_when_saved() {
+    let sandbox = Sandbox::new("migration-backup");
+    let state_path = sandbox.directory.join("state.json");
+    let backup_path = sandbox.directory.join("state.json.v0.bak");
+    std::fs::write(&state_path, unversioned_state()).unwrap();
+
+    for args in [
+        &["status", "--tmux"][..],
+        &["stats"],
+        &["history", "--json"],
+    ] {
+        assert_success(&sandbox.run(args, ""));
+    }
+    assert!(!backup_path.exists());
+    assert_eq!(
+        std::fs::read_to_string(&state_path).unwrap(),
+        unversioned_state()
+    );
+
+    assert_success(&sandbox.run(&["ack", "--stdin"], "PR_a\n"));
+    assert_eq!(
+        std::fs::read_to_string(&backup_path).unwrap(),
+        unversioned_state()
+    );
+    let state: Value =
+        serde_json::from_str(&std::fs::read_to_string(&state_path).unwrap()).unwrap();
+    assert_eq!(state["version"], STATE_VERSION);
+
+    // The upgraded state needs no further backups
+    std::fs::remove_file(&backup_path).unwrap();
+    assert_success(&sandbox.run(&["ack", "--stdin"], "PR_b\n"));
+    assert!(!backup_path.exists());
+}
+
+#[test]
+fn legacy_state_is_only_moved_under_the_lock() {
+    let sandbox = Sandbox::new("migration-legacy");
+    assert_success(&sandbox.run(&["ack", "--stdin"], "PR_b\n"));
+
+    // Older versions kept the default state file in the config directory
+    let config_directory = sandbox.directory.join("config");
+    let state_directory = sandbox.directory.join("state");
+    std::fs::create_dir_all(&config_directory).unwrap();
+    let legacy_path = config_directory.join("ghprs-state.json");
+    let state_path = state_directory.join("ghprs-state.json");
+    std::fs::copy(sandbox.directory.join("state.json"), &legacy_path).unwrap();
+    let config_path = sandbox.directory.join("default-state.toml");
+    std::fs::write(
+        &config_path,
+        format!(
+            "author = \"me\"\nrepositories = [\"org/a\", \"org/b\"]\nfixture_file = {:?}\n",
+            sandbox.directory.join("fixture.json"),
+        ),
+    )
+    .unwrap();
+    let run = |args: &[&str]| {
+        sandbox
+            .ghp(args)
+            .env("GHPRS_CONFIG_FILE", &config_path)
+            .env("XDG_CONFIG_HOME", &config_directory)
+            .env("XDG_STATE_HOME", &state_directory)
+            .output()
+            .unwrap()
+    };
+
+    let status = run(&["status", "--tmux"]);
+    assert_success(&status);
+    assert!(String::from_utf8_lossy(&status.stdout).contains("PR 1"));
+    assert!(legacy_path.exists());
+    assert!(!state_path.exists());
+
+    assert_success(&run(&["fetch-acked", "--porcelain"]));
+    assert!(!legacy_path.exists());
+    assert!(std::fs::read_to_string(&state_path)
+        .unwrap()
+        .contains("PR_b"));
+}